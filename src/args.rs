@@ -0,0 +1,768 @@
+/*
+    Shared parser for command arguments
+
+    every command declares its arguments as ArgSpecs in the registry, the dispatcher parses the message against them
+    and hands the typed values to the handler, anything that doesn't fit the declaration is reported back by name
+
+    syntax:  positional   -flag   switch   key=value   key="quoted value"   key=[bracketed value]   <@user>
+*/
+use std::collections::HashMap;
+use std::fmt;
+
+use chrono::{NaiveDate, NaiveTime};
+use serenity::all::UserId;
+
+use crate::lang::{tr, Lang};
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Enum Declaration
+
+// Type of an argument, decides how it is parsed, the slash option type and how help displays it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArgKind {
+    Text,
+    Int,
+    Float,
+    Bool,
+    User,
+    Timestamp,      // 90 / 1:23.5 / 1m30.25s, kept in milliseconds
+    Date,           // DD/MM/YYYY
+    Time,           // HH:MM
+    Enum,           // One of the declared choices
+}
+
+// How an argument is written in the `$` form of a command
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Render {
+    Positional,     // value
+    Flag,           // -value
+    Switch,         // name, a Bool that is true when the name is given
+    Key,            // key=value
+    Bracketed,      // key=[value]
+    Mention,        // <@value>
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArgValue {
+    Text(String),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    User(UserId),
+    Timestamp(u64),
+    Date(NaiveDate),
+    Time(NaiveTime),
+}
+
+// Why an argument was rejected, rendered through the catalog in the language of the guild
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArgReason {
+    UnknownOption,
+    UnknownFlag,
+    UnexpectedMention,
+    UnexpectedArgument,
+    Required,
+    RequiredFor(String),
+    Duplicate,
+    Range(u64, u64),
+    Conflict(String),           // The other argument that can't be given at the same time
+    Unclosed(char),
+    Unquotable,                 // Holds a " as well as brackets that don't match, neither form can wrap it
+    Invalid(String, Expected),  // The raw value and what was expected instead
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expected {
+    Number,
+    Decimal,
+    Bool,
+    User,
+    Timestamp,
+    After(String),              // A timestamp after the given one
+    Between(String, String),    // A number from min to max, for ranges the spec can't hold
+    Date,
+    Time,
+    Id,
+    Name,
+    OneOf(String),
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Struct Declaration
+
+#[derive(Debug)]
+pub struct ArgSpec {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub kind: ArgKind,
+    pub render: Render,
+    pub required: bool,
+    pub choices: &'static [&'static str],
+    pub range: Option<(u64, u64)>,
+    pub default: Option<&'static str>,  // Parsed like user input if the argument is missing
+    pub rest: bool,                     // Positional that takes the remainder of the message as is
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArgError {
+    pub key: String,
+    pub reason: ArgReason,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ParsedArgs {
+    values: HashMap<&'static str, ArgValue>,
+}
+
+// A single piece of the argument string
+#[derive(Debug)]
+enum Token<'a> {
+    Word(&'a str),
+    Flag(&'a str),
+    Option(&'a str, String),
+    Mention(&'a str),
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Argument builders for the registry
+
+pub const fn arg(name: &'static str, description: &'static str, kind: ArgKind, render: Render) -> ArgSpec {
+    ArgSpec { name, description, kind, render, required: false, choices: &[], range: None, default: None, rest: false }
+}
+
+pub const fn req(mut a: ArgSpec) -> ArgSpec {
+    a.required = true;
+    a
+}
+
+pub const fn choices(mut a: ArgSpec, list: &'static [&'static str]) -> ArgSpec {
+    a.choices = list;
+    a
+}
+
+pub const fn range(mut a: ArgSpec, min: u64, max: u64) -> ArgSpec {
+    a.range = Some((min, max));
+    a
+}
+
+pub const fn fallback(mut a: ArgSpec, value: &'static str) -> ArgSpec {
+    a.default = Some(value);
+    a
+}
+
+pub const fn remainder(mut a: ArgSpec) -> ArgSpec {
+    a.rest = true;
+    a
+}
+
+
+impl ArgError {
+    pub fn new(key: &str, reason: ArgReason) -> ArgError {
+        ArgError { key: key.to_string(), reason }
+    }
+
+    pub fn invalid(key: &str, raw: &str, expected: Expected) -> ArgError {
+        ArgError::new(key, ArgReason::Invalid(raw.to_string(), expected))
+    }
+
+    pub fn render(&self, lang: Lang) -> String {
+
+        let key: &str = self.key.as_str();
+
+        match &self.reason {
+            ArgReason::UnknownOption => tr(lang, "arg.unknown_option", &[("key", key)]),
+            ArgReason::UnknownFlag => tr(lang, "arg.unknown_flag", &[("key", key)]),
+            ArgReason::UnexpectedMention => tr(lang, "arg.unexpected_mention", &[("key", key)]),
+            ArgReason::UnexpectedArgument => tr(lang, "arg.unexpected_argument", &[("key", key)]),
+            ArgReason::Required => tr(lang, "arg.required", &[("key", key)]),
+            ArgReason::RequiredFor(C) => tr(lang, "arg.required_for", &[("key", key), ("context", C)]),
+            ArgReason::Duplicate => tr(lang, "arg.duplicate", &[("key", key)]),
+            ArgReason::Range(min, max) => tr(lang, "arg.range", &[("key", key), ("min", &min.to_string()), ("max", &max.to_string())]),
+            ArgReason::Conflict(O) => tr(lang, "arg.conflict", &[("key", key), ("other", O)]),
+            ArgReason::Unclosed(C) => tr(lang, "arg.unclosed", &[("key", key), ("close", &C.to_string())]),
+            ArgReason::Unquotable => tr(lang, "arg.unquotable", &[("key", key)]),
+            ArgReason::Invalid(raw, expected) => {
+                let expected: String = match expected {
+                    Expected::Number => tr(lang, "arg.expected_number", &[]),
+                    Expected::Decimal => tr(lang, "arg.expected_decimal", &[]),
+                    Expected::Bool => tr(lang, "arg.expected_bool", &[]),
+                    Expected::User => tr(lang, "arg.expected_user", &[]),
+                    Expected::Timestamp => tr(lang, "arg.expected_timestamp", &[]),
+                    Expected::After(T) => tr(lang, "arg.expected_after", &[("time", T)]),
+                    Expected::Between(min, max) => tr(lang, "arg.expected_between", &[("min", min), ("max", max)]),
+                    Expected::Date => tr(lang, "arg.expected_date", &[]),
+                    Expected::Time => tr(lang, "arg.expected_time", &[]),
+                    Expected::Id => tr(lang, "arg.expected_id", &[]),
+                    Expected::Name => tr(lang, "arg.expected_name", &[("max", &crate::intro::MAX_NAME.to_string())]),
+                    Expected::OneOf(C) => tr(lang, "arg.expected_one_of", &[("choices", C)]),
+                };
+                tr(lang, "arg.invalid", &[("key", key), ("value", raw), ("expected", &expected)])
+            },
+        }
+    }
+}
+
+impl fmt::Display for ArgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.render(Lang::En))
+    }
+}
+
+impl ParsedArgs {
+
+    pub fn has(&self, key: &str) -> bool {
+        self.values.contains_key(key)
+    }
+
+    // Defaults count as well, so this is only empty for commands without any that got nothing
+    pub fn isEmpty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn text(&self, key: &str) -> Option<&str> {
+        match self.values.get(key) {
+            Some(ArgValue::Text(T)) => Some(T.as_str()),
+            _ => None,
+        }
+    }
+
+    pub fn int(&self, key: &str) -> Option<i64> {
+        match self.values.get(key) {
+            Some(ArgValue::Int(I)) => Some(*I),
+            _ => None,
+        }
+    }
+
+    pub fn float(&self, key: &str) -> Option<f64> {
+        match self.values.get(key) {
+            Some(ArgValue::Float(F)) => Some(*F),
+            _ => None,
+        }
+    }
+
+    pub fn user(&self, key: &str) -> Option<UserId> {
+        match self.values.get(key) {
+            Some(ArgValue::User(U)) => Some(*U),
+            _ => None,
+        }
+    }
+
+    pub fn bool(&self, key: &str) -> Option<bool> {
+        match self.values.get(key) {
+            Some(ArgValue::Bool(B)) => Some(*B),
+            _ => None,
+        }
+    }
+
+    // In milliseconds
+    pub fn timestamp(&self, key: &str) -> Option<u64> {
+        match self.values.get(key) {
+            Some(ArgValue::Timestamp(T)) => Some(*T),
+            _ => None,
+        }
+    }
+
+    pub fn date(&self, key: &str) -> Option<NaiveDate> {
+        match self.values.get(key) {
+            Some(ArgValue::Date(D)) => Some(*D),
+            _ => None,
+        }
+    }
+
+    pub fn time(&self, key: &str) -> Option<NaiveTime> {
+        match self.values.get(key) {
+            Some(ArgValue::Time(T)) => Some(*T),
+            _ => None,
+        }
+    }
+
+    // For arguments that are only required in some modes of a command
+    pub fn require(&self, key: &str, context: &str) -> Result<&ArgValue, ArgError> {
+        self.values.get(key).ok_or(ArgError::new(key, ArgReason::RequiredFor(context.to_string())))
+    }
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Parses the arguments of a command against its declaration
+pub fn parseArgs(specs: &'static [ArgSpec], input: &str) -> Result<ParsedArgs, ArgError> {
+
+    let mut parsed: ParsedArgs = ParsedArgs::default();
+    let mut positionals = specs.iter().filter(|s| s.render == Render::Positional).peekable();
+
+    let mut pos: usize = 0;
+
+    while let Some((start, token)) = nextToken(input, &mut pos)? {
+
+        let (spec, raw, err): (Option<&'static ArgSpec>, &str, ArgError) = match &token {
+            Token::Option(key, value) => (
+                specs.iter().find(|s| s.name == *key && matches!(s.render, Render::Key | Render::Bracketed)),
+                value.as_str(),
+                ArgError::new(key, ArgReason::UnknownOption),
+            ),
+            Token::Flag(flag) => (
+                specs.iter().find(|s| s.render == Render::Flag),
+                flag,
+                ArgError::new(flag, ArgReason::UnknownFlag),
+            ),
+            Token::Mention(id) => (
+                specs.iter().find(|s| s.render == Render::Mention && !parsed.has(s.name)),
+                id,
+                ArgError::new(id, ArgReason::UnexpectedMention),
+            ),
+            Token::Word(word) => match specs.iter().find(|s| s.render == Render::Switch && s.name.eq_ignore_ascii_case(word)) {
+                Some(S) => (Some(S), "1", ArgError::new(word, ArgReason::UnexpectedArgument)),
+                None => (
+                    positionals.next_if(|s| !s.rest),
+                    word,
+                    ArgError::new(word, ArgReason::UnexpectedArgument),
+                ),
+            },
+        };
+
+        if let Some(spec) = spec {
+            insertValue(&mut parsed, spec, raw)?;
+            continue;
+        }
+
+        // Free text like $say takes everything from here on, even if it looks like an option
+        match positionals.next_if(|s| s.rest) {
+            Some(spec) => {
+                insertValue(&mut parsed, spec, input[start..].trim())?;
+                break;
+            },
+            None => return Err(err),
+        }
+    }
+
+    // Fills in defaults and checks for missing arguments
+    for spec in specs {
+
+        if parsed.has(spec.name) {
+            continue;
+        }
+
+        if let Some(default) = spec.default {
+            insertValue(&mut parsed, spec, default)?;
+        } else if spec.required {
+            return Err(ArgError::new(spec.name, ArgReason::Required));
+        }
+    }
+
+    Ok(parsed)
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Converts a raw value into the type of its argument and stores it
+fn insertValue(parsed: &mut ParsedArgs, spec: &'static ArgSpec, raw: &str) -> Result<(), ArgError> {
+
+    if parsed.has(spec.name) {
+        return Err(ArgError::new(spec.name, ArgReason::Duplicate));
+    }
+
+    let value: ArgValue = parseValue(spec, raw)?;
+
+    if let (ArgValue::Int(I), Some((min, max))) = (&value, spec.range) {
+        if *I < min as i64 || *I > max as i64 {
+            return Err(ArgError::new(spec.name, ArgReason::Range(min, max)));
+        }
+    }
+
+    // The range of a timestamp is given in seconds
+    if let (ArgValue::Timestamp(T), Some((min, max))) = (&value, spec.range) {
+        if *T < min * 1000 || *T > max * 1000 {
+            return Err(ArgError::new(spec.name, ArgReason::Range(min, max)));
+        }
+    }
+
+    parsed.values.insert(spec.name, value);
+
+    Ok(())
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Parses a single value
+fn parseValue(spec: &ArgSpec, raw: &str) -> Result<ArgValue, ArgError> {
+
+    let invalid = |expected: Expected| ArgError::invalid(spec.name, raw, expected);
+
+    match spec.kind {
+        ArgKind::Text => Ok(ArgValue::Text(raw.to_string())),
+        ArgKind::Int => raw.parse::<i64>().map(ArgValue::Int).map_err(|_| invalid(Expected::Number)),
+        ArgKind::Float => raw.parse::<f64>().ok()
+                                            .filter(|f| f.is_finite())
+                                            .map(ArgValue::Float)
+                                            .ok_or(invalid(Expected::Decimal)),
+        ArgKind::Bool => match raw.to_lowercase().as_str() {
+            "1" | "true" | "yes" | "on" => Ok(ArgValue::Bool(true)),
+            "0" | "false" | "no" | "off" => Ok(ArgValue::Bool(false)),
+            _ => Err(invalid(Expected::Bool)),
+        },
+        ArgKind::User => raw.trim_start_matches("<@")
+                            .trim_start_matches('!')
+                            .trim_end_matches('>')
+                            .parse::<u64>()
+                            .ok()
+                            .filter(|id| *id != 0)
+                            .map(|id| ArgValue::User(UserId::new(id)))
+                            .ok_or(invalid(Expected::User)),
+        ArgKind::Timestamp => parseTimestamp(raw).map(ArgValue::Timestamp).ok_or(invalid(Expected::Timestamp)),
+        ArgKind::Date => NaiveDate::parse_from_str(raw, "%d/%m/%Y").map(ArgValue::Date).map_err(|_| invalid(Expected::Date)),
+        ArgKind::Time => NaiveTime::parse_from_str(raw, "%H:%M").map(ArgValue::Time).map_err(|_| invalid(Expected::Time)),
+        ArgKind::Enum => spec.choices.iter()
+                                     .find(|c| c.eq_ignore_ascii_case(raw))
+                                     .map(|c| ArgValue::Text(c.to_string()))
+                                     .ok_or(invalid(Expected::OneOf(spec.choices.join(", ")))),
+    }
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Parses 90 / 1:30 / 1:02:03 / 1h2m3s into seconds, fractions are cut off
+pub fn parseDuration(raw: &str) -> Option<u64> {
+    parseTimestamp(raw).map(|ms| ms / 1000)
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Parses 90 / 3.25 / 1:23.5 / 1:02:03.250 / 1h2m3.5s into milliseconds, only the seconds may have a fraction
+pub fn parseTimestamp(raw: &str) -> Option<u64> {
+
+    if raw.is_empty() {
+        return None;
+    }
+
+    if let Some(ms) = parseSeconds(raw) {
+        return Some(ms);
+    }
+
+    if raw.contains(':') {
+        let parts: Vec<&str> = raw.split(':').collect();
+
+        if parts.len() > 3 {
+            return None;
+        }
+
+        let (seconds, rest): (&&str, &[&str]) = parts.split_last()?;
+        let seconds: u64 = parseSeconds(seconds)?;
+
+        // Only the first part may go past 59, 1:75 is a typo and not 2:15
+        if seconds >= 60_000 {
+            return None;
+        }
+
+        let whole: u64 = rest.iter().enumerate().try_fold(0u64, |acc, (i, p)| {
+            let value: u64 = p.parse::<u64>().ok()?;
+
+            if i > 0 && value >= 60 {
+                return None;
+            }
+
+            acc.checked_mul(60)?.checked_add(value)
+        })?;
+
+        return whole.checked_mul(60_000)?.checked_add(seconds);
+    }
+
+    let mut total: u64 = 0;
+    let mut number: String = String::new();
+
+    for c in raw.chars() {
+        match c {
+            '0'..='9' | '.' => number.push(c),
+            'h' | 'm' => {
+                let value: u64 = number.parse::<u64>().ok()?;
+                number.clear();
+
+                let ms: u64 = match c {
+                    'h' => value.checked_mul(3_600_000)?,
+                    _ => value.checked_mul(60_000)?,
+                };
+
+                total = total.checked_add(ms)?;
+            },
+            's' => {
+                total = total.checked_add(parseSeconds(&number)?)?;
+                number.clear();
+            },
+            _ => return None,
+        }
+    }
+
+    if !number.is_empty() {
+        return None;
+    }
+
+    Some(total)
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Parses 90 / 3.25 / .5 into milliseconds, digits past the third decimal are cut off
+fn parseSeconds(raw: &str) -> Option<u64> {
+
+    let (whole, fraction): (&str, &str) = raw.split_once('.').unwrap_or((raw, ""));
+
+    if whole.is_empty() && fraction.is_empty() {
+        return None;
+    }
+
+    if !whole.chars().chain(fraction.chars()).all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let secs: u64 = if whole.is_empty() { 0 } else { whole.parse::<u64>().ok()? };
+    let millis: u64 = format!("{:0<3}", &fraction[..fraction.len().min(3)]).parse::<u64>().ok()?;
+
+    secs.checked_mul(1000)?.checked_add(millis)
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Formats milliseconds as H:MM:SS.mmm, which yt-dlp and ffmpeg both understand
+pub fn formatTimestamp(ms: u64) -> String {
+
+    let hours: u64 = ms / 3_600_000;
+    let minutes: u64 = ms / 60_000 % 60;
+    let seconds: u64 = ms / 1000 % 60;
+
+    format!("{}:{:02}:{:02}.{:03}", hours, minutes, seconds, ms % 1000)
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Reads the next token and returns it with its start offset
+fn nextToken<'a>(input: &'a str, pos: &mut usize) -> Result<Option<(usize, Token<'a>)>, ArgError> {
+
+    let rest: &str = &input[*pos..];
+    let trimmed: &str = rest.trim_start();
+
+    if trimmed.is_empty() {
+        *pos = input.len();
+        return Ok(None);
+    }
+
+    let start: usize = *pos + (rest.len() - trimmed.len());
+    let word_end: usize = trimmed.find(char::is_whitespace).unwrap_or(trimmed.len());
+    let word: &str = &trimmed[..word_end];
+
+    // key=value, key="value" and key=[value]
+    if let Some(eq) = word.find('=') {
+
+        let key: &str = &word[..eq];
+
+        if !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+
+            let after: &str = &trimmed[eq + 1..];
+
+            let (value, used): (String, usize) = if let Some(inner) = after.strip_prefix('[') {
+                let close: usize = findClosing(inner).ok_or(ArgError::new(key, ArgReason::Unclosed(']')))?;
+                (inner[..close].to_string(), close + 2)
+            } else if let Some(inner) = after.strip_prefix('"') {
+                let close: usize = inner.find('"').ok_or(ArgError::new(key, ArgReason::Unclosed('"')))?;
+                (inner[..close].to_string(), close + 2)
+            } else {
+                let end: usize = after.find(char::is_whitespace).unwrap_or(after.len());
+                (after[..end].to_string(), end)
+            };
+
+            *pos = start + eq + 1 + used;
+            return Ok(Some((start, Token::Option(key, value))));
+        }
+    }
+
+    *pos = start + word_end;
+
+    if word.starts_with("<@") && word.ends_with('>') {
+        return Ok(Some((start, Token::Mention(word))));
+    }
+
+    if let Some(flag) = word.strip_prefix('-') {
+        if flag.chars().next().is_some_and(|c| c.is_alphabetic()) {
+            return Ok(Some((start, Token::Flag(flag))));
+        }
+    }
+
+    Ok(Some((start, Token::Word(word))))
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Writes a value so nextToken reads it back as one, None if neither [value] nor "value" can hold it
+pub fn quoteValue(value: &str) -> Option<String> {
+
+    if !value.is_empty() && !value.contains(char::is_whitespace) && !value.starts_with(['[', '"']) {
+        return Some(value.to_string());
+    }
+
+    // Brackets inside are fine as long as every one is closed again
+    if findClosing(&format!("{}]", value)) == Some(value.len()) {
+        return Some(format!("[{}]", value));
+    }
+
+    if !value.contains('"') {
+        return Some(format!("\"{}\"", value));
+    }
+
+    None
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Finds the ] that closes an already opened [, nested brackets are kept in the value
+fn findClosing(inner: &str) -> Option<usize> {
+
+    let mut depth: usize = 0;
+
+    for (i, c) in inner.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' if depth == 0 => return Some(i),
+            ']' => depth -= 1,
+            _ => (),
+        }
+    }
+
+    None
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPECS: &[ArgSpec] = &[
+        req(choices(arg("mode", "What to do", ArgKind::Enum, Render::Positional), &["add", "list"])),
+        arg("user", "Whose", ArgKind::User, Render::Mention),
+        range(arg("count", "How many", ArgKind::Int, Render::Key), 1, 10),
+        arg("name", "Name", ArgKind::Text, Render::Bracketed),
+        arg("quiet", "No answer", ArgKind::Bool, Render::Switch),
+    ];
+
+    const FLAGGED: &[ArgSpec] = &[
+        req(choices(arg("mode", "What to do", ArgKind::Enum, Render::Flag), &["list", "add"])),
+    ];
+
+    const STRICT: &[ArgSpec] = &[
+        arg("count", "How many", ArgKind::Int, Render::Key),
+    ];
+
+    fn error(specs: &'static [ArgSpec], input: &str) -> ArgError {
+        parseArgs(specs, input).unwrap_err()
+    }
+
+    #[test]
+    fn parsesEveryForm() {
+
+        let args: ParsedArgs = parseArgs(SPECS, "ADD <@!5> count=3 name=[two [nested] words] quiet").unwrap();
+
+        assert_eq!(args.text("mode"), Some("add"));
+        assert_eq!(args.user("user"), Some(UserId::new(5)));
+        assert_eq!(args.int("count"), Some(3));
+        assert_eq!(args.text("name"), Some("two [nested] words"));
+        assert_eq!(args.bool("quiet"), Some(true));
+
+        assert_eq!(parseArgs(SPECS, "list name=\"quoted value\"").unwrap().text("name"), Some("quoted value"));
+        assert_eq!(parseArgs(FLAGGED, "-LIST").unwrap().text("mode"), Some("list"));
+    }
+
+    #[test]
+    fn remainderTakesTheMessageAsIs() {
+
+        let say: &[ArgSpec] = crate::command::findCommand("say").unwrap().args;
+
+        assert_eq!(parseArgs(say, "  hello count=3 -x <@5> ").unwrap().text("text"), Some("hello count=3 -x <@5>"));
+        assert_eq!(error(say, ""), ArgError::new("text", ArgReason::Required));
+    }
+
+    #[test]
+    fn errorsNameTheKey() {
+        assert_eq!(error(SPECS, "add color=red"), ArgError::new("color", ArgReason::UnknownOption));
+        assert_eq!(error(SPECS, "add count=1 count=2"), ArgError::new("count", ArgReason::Duplicate));
+        assert_eq!(error(SPECS, "add name=[open"), ArgError::new("name", ArgReason::Unclosed(']')));
+        assert_eq!(error(SPECS, "add name=\"open"), ArgError::new("name", ArgReason::Unclosed('"')));
+        assert_eq!(error(SPECS, "quiet"), ArgError::new("mode", ArgReason::Required));
+        assert_eq!(error(SPECS, "remove"), ArgError::invalid("mode", "remove", Expected::OneOf("add, list".to_string())));
+        assert_eq!(error(SPECS, "add more"), ArgError::new("more", ArgReason::UnexpectedArgument));
+
+        assert_eq!(error(FLAGGED, "list"), ArgError::new("list", ArgReason::UnexpectedArgument));
+        assert_eq!(error(FLAGGED, "-list -add"), ArgError::new("mode", ArgReason::Duplicate));
+
+        assert_eq!(error(STRICT, "-v"), ArgError::new("v", ArgReason::UnknownFlag));
+        assert_eq!(error(STRICT, "<@5>"), ArgError::new("<@5>", ArgReason::UnexpectedMention));
+    }
+
+    #[test]
+    fn quotedValuesReadBackTheSame() {
+
+        for value in ["plain", "two words", "a]b", "[x] y", "say \"hi\"", "\"", "a [b] \"c\"", "", "[open", "close]"] {
+            let quoted: String = quoteValue(value).unwrap();
+            let parsed: ParsedArgs = parseArgs(SPECS, &format!("add name={} count=2", quoted)).unwrap();
+
+            assert_eq!(parsed.text("name"), Some(value), "{}", quoted);
+            assert_eq!(parsed.int("count"), Some(2), "{}", quoted);
+        }
+
+        assert_eq!(quoteValue("\" ]"), None);
+        assert_eq!(quoteValue("[\""), None);
+    }
+
+    #[test]
+    fn valuesAreChecked() {
+        assert_eq!(error(SPECS, "add count=0"), ArgError::new("count", ArgReason::Range(1, 10)));
+        assert_eq!(error(SPECS, "add count=11"), ArgError::new("count", ArgReason::Range(1, 10)));
+        assert_eq!(error(SPECS, "add count=x"), ArgError::invalid("count", "x", Expected::Number));
+        assert_eq!(error(SPECS, "add <@0>"), ArgError::invalid("user", "<@0>", Expected::User));
+
+        // Timestamp ranges are declared in seconds
+        let length: &'static [ArgSpec] = &crate::command::findCommand("setvideo").unwrap().args[2..3];
+        assert_eq!(parseArgs(length, "length=2.5").unwrap().timestamp("length"), Some(2_500));
+        assert_eq!(error(length, "length=10.5"), ArgError::new("length", ArgReason::Range(1, 10)));
+        assert_eq!(error(length, "length=0.5"), ArgError::new("length", ArgReason::Range(1, 10)));
+    }
+
+    #[test]
+    fn timestampForms() {
+        assert_eq!(parseTimestamp("90"), Some(90_000));
+        assert_eq!(parseTimestamp("3.25"), Some(3_250));
+        assert_eq!(parseTimestamp(".5"), Some(500));
+        assert_eq!(parseTimestamp("1:23.5"), Some(83_500));
+        assert_eq!(parseTimestamp("1:02:03.250"), Some(3_723_250));
+        assert_eq!(parseTimestamp("75:30"), Some(4_530_000));
+        assert_eq!(parseTimestamp("1h2m3.5s"), Some(3_723_500));
+        assert_eq!(parseTimestamp("1.23456"), Some(1_234));
+    }
+
+    #[test]
+    fn timestampRejectsGarbage() {
+        assert_eq!(parseTimestamp(""), None);
+        assert_eq!(parseTimestamp("."), None);
+        assert_eq!(parseTimestamp("1:2:3:4"), None);
+        assert_eq!(parseTimestamp("1m30"), None);
+        assert_eq!(parseTimestamp("abc"), None);
+    }
+
+    #[test]
+    fn timestampRejectsOutOfRangeParts() {
+        assert_eq!(parseTimestamp("1:75"), None);
+        assert_eq!(parseTimestamp("1:60"), None);
+        assert_eq!(parseTimestamp("1:59.999"), Some(119_999));
+        assert_eq!(parseTimestamp("1:75:00"), None);
+        assert_eq!(parseTimestamp("100:59:59"), Some(363_599_000));
+    }
+
+    #[test]
+    fn timestampOverflowIsNone() {
+        assert_eq!(parseTimestamp("99999999999999999"), None);
+        assert_eq!(parseTimestamp("9999999999999h"), None);
+        assert_eq!(parseTimestamp("999999999999999m"), None);
+        assert_eq!(parseTimestamp("1h99999999999999999s"), None);
+        assert_eq!(parseTimestamp("99999999999999:00:00"), None);
+        assert_eq!(parseSeconds("18446744073709552"), None);
+    }
+}
@@ -0,0 +1,129 @@
+/*
+    Time source for the event thread
+
+    SystemClock follows the wall clock, FakeClock only moves when it is told to so schedules can be tried out in a simulation
+    a FakeClock that is advanced past several events stops at each of them in order, so none of them are skipped
+*/
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{Local, NaiveDate, NaiveDateTime};
+use serenity::prelude::{RwLock, TypeMap, TypeMapKey};
+
+use crate::helper::EventSignal;
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Const Declaration
+
+// How often a waiting FakeClock checks if it has been advanced
+const FAKE_POLL: Duration = Duration::from_millis(50);
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Trait Declaration
+
+pub trait Clock: Send + Sync {
+
+    fn now(&self) -> NaiveDateTime;
+
+    // Waits for a signal until the clock has moved on by secs
+    fn wait(&self, recv: &Receiver<EventSignal>, secs: u64) -> Result<EventSignal, RecvTimeoutError>;
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Struct Declaration
+
+pub struct SystemClock;
+
+pub struct FakeClock {
+    now: Mutex<NaiveDateTime>,
+    target: Mutex<NaiveDateTime>,       // Where advance wants the clock to be, now catches up to it one wait at a time
+}
+
+pub struct ClockKey;
+
+impl TypeMapKey for ClockKey {
+    type Value = Arc<dyn Clock>;
+}
+
+
+impl Clock for SystemClock {
+
+    fn now(&self) -> NaiveDateTime {
+        Local::now().naive_local()
+    }
+
+    fn wait(&self, recv: &Receiver<EventSignal>, secs: u64) -> Result<EventSignal, RecvTimeoutError> {
+        recv.recv_timeout(Duration::from_secs(secs))
+    }
+}
+
+
+impl FakeClock {
+
+    pub fn new(start: NaiveDateTime) -> FakeClock {
+        FakeClock { now: Mutex::new(start), target: Mutex::new(start) }
+    }
+
+    pub fn advance(&self, secs: u64) {
+        *self.target.lock().unwrap() += chrono::Duration::seconds(secs as i64);
+    }
+
+    // True once the event thread has worked through everything up to the target
+    pub fn caughtUp(&self) -> bool {
+        *self.now.lock().unwrap() >= *self.target.lock().unwrap()
+    }
+}
+
+impl Clock for FakeClock {
+
+    fn now(&self) -> NaiveDateTime {
+        *self.now.lock().unwrap()
+    }
+
+    fn wait(&self, recv: &Receiver<EventSignal>, secs: u64) -> Result<EventSignal, RecvTimeoutError> {
+
+        let deadline: NaiveDateTime = self.now() + chrono::Duration::seconds(secs as i64);
+
+        loop {
+            match recv.recv_timeout(FAKE_POLL) {
+                Err(RecvTimeoutError::Timeout) => (),
+                other => return other,
+            }
+
+            let target: NaiveDateTime = *self.target.lock().unwrap();
+            let mut now = self.now.lock().unwrap();
+
+            // Stops at the deadline so the event that is due runs before the clock moves any further
+            if target >= deadline {
+                *now = deadline;
+                return Err(RecvTimeoutError::Timeout);
+            }
+
+            *now = target;
+        }
+    }
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Gets the current day from the clock in the shared data, the wall clock if there is none
+pub async fn today(data: &RwLock<TypeMap>) -> NaiveDate {
+    match data.read().await.get::<ClockKey>() {
+        Some(C) => C.now().date(),
+        None => Local::now().date_naive(),
+    }
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Gets the current time from the clock in the shared data, the wall clock if there is none
+pub async fn now(data: &RwLock<TypeMap>) -> NaiveDateTime {
+    match data.read().await.get::<ClockKey>() {
+        Some(C) => C.now(),
+        None => Local::now().naive_local(),
+    }
+}
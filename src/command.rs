@@ -1,24 +1,16 @@
 use std::collections::HashMap;
-use std::{path::Path, process::Command};
-use std::fmt::Error;
+use std::path::Path;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
 
 #[cfg(feature = "old_downloader")]
-use std::fs;
-
-use tokio::task;
-use strum::{IntoEnumIterator, EnumCount};
-use strum_macros::{EnumIter, Display};
-use regex::Regex;
+use std::{fs, process::Command};
 
 #[cfg(feature = "old_downloader")]
 use rustube::{VideoFetcher, Id};
 
-#[cfg(feature = "old_downloader")]
-use hrtime;
-
-use serenity::{client::*, model::channel::*};
 use serenity::prelude::TypeMapKey;
-use youtube_dl::YoutubeDl;
 
 use crate::helper::*;
 use crate::predict::*;
@@ -27,75 +19,69 @@ use crate::poll::*;
 use crate::fortnite::*;
 use crate::event::*;
 use crate::santa::santaHandler;
+use crate::voice::{disconnectVoice, soundsHandler, stopSound, volumeHandler};
+use crate::intro::{checkName, deleteSoundFiles, introFile, introHandler, listGuild, soundKind, sourceFile, versionFile, writeIntros, IntroInfo, IntroLibrary, IntroSound, IntroVersion, SoundKind, MAX_INTROS};
+use crate::clock;
+use crate::effects::{effectChain, loudnessOptions, renderIntro};
+use crate::download::{cutAudio, downloadSection, fetchInfo, probeDuration, CancelToken};
+use crate::jobs::{cancelJob, checkRunning, jobStatus, startJob, JobHandle, JobStatus};
+use crate::args::*;
+use crate::permission::checkPermission;
+use crate::cooldown::{acquireSlot, Slot};
+use crate::lang::{describe, tr, Lang};
+use crate::error::RinrError;
+use crate::gateway::{AttachmentInfo, Invocation};
 
 
 //--------------------------------------------------------------------------------------------------------------------------
 // Const Declaration
 
-const TEST: &str = "$test";
-const SET: &str = "$setvideo ";
-const LIST: &str = "$list ";
-const DISCONNECT: &str = "$disconnect ";
-const STFU: &str = "STFU";
-const KYS: &str = "kys";
-const TIMER: &str = "$timer ";
-const WIN: &str = "$win";
-const BAN: &str = "$ban ";
-const ULIST: &str = "$userlist ";
-const SAY: &str = "$say ";
-pub const PREDICTION: &str = "$predict ";
-const POLL: &str = "$poll ";
-const FORTNITE: &str = "$fn ";
-const EVENT: &str = "$event ";
-const SANTA: &str = "$santa";
+const PREFIX: char = '$';
+
+// Attachments $setvideo accepts instead of a link
+const UPLOAD_TYPES: &[&str] = &["mp3", "ogg", "wav", "m4a", "webm"];
+pub const MAX_UPLOAD_MB: u64 = 25;
+pub const MAX_UPLOAD_MINUTES: u64 = 15;
+
+// Clip length in seconds, used when neither length nor end is given and as the allowed range
+const DEFAULT_CLIP: u64 = 5;
+const MIN_CLIP: u64 = 1;
+const MAX_CLIP: u64 = 10;
 
 //const TEST_RESPONSE: &str = "Pissing all by yourself handsome?";
-const SET_RESPONSE: &str = "New video set!\nFor User: ";
 
 //const YT: &str = "https://youtu.be/";
 
-const CONSTS: &'static [&str] = &[TEST, SET, LIST, DISCONNECT, STFU,
-                                  KYS, TIMER, WIN, BAN, ULIST, SAY,
-                                  PREDICTION, POLL, FORTNITE, EVENT, SANTA];
-
 
 //--------------------------------------------------------------------------------------------------------------------------
-// Enum Declaration 
-
-#[derive(Debug, EnumIter, Display, EnumCount, PartialEq)]
-pub enum COMMAND {
-    E_TEST,
-    E_SET,
-    E_LIST,
-    E_DISCONNECT,
-    E_STFU,
-    E_KYS,
-    E_TIMER,
-    E_WIN,
-    E_BAN,
-    E_ULIST,
-    E_SAY,
-    E_PREDICTION,
-    E_POLL,
-    E_FORTNITE,
-    E_EVENT,
-    E_SANTA,
-    INVALID,
-}
+// Struct Declaration 
 
+pub type CommandFn = for<'a> fn(&'a Invocation, &'a ParsedArgs) -> Pin<Box<dyn Future<Output = Result<(), RinrError>> + Send + 'a>>;
 
-//--------------------------------------------------------------------------------------------------------------------------
-// Struct Declaration 
+pub struct CommandSpec {
+    pub name: &'static str,
+    pub aliases: &'static [&'static str],
+    pub prefixed: bool,                 // false for commands that are just a word in chat, like STFU
+    pub usage: &'static str,
+    pub args: &'static [ArgSpec],
+    pub handler: Option<CommandFn>,     // None if not implemented yet
+}
 
 #[derive(Debug)]
 pub struct VidInfo {
     name: String,       // Video name
-    v_length: u64,      // Video length          
-    start: u64,         // User start point     Default: 0
-    u_length: u64,      // Clip length          Default: 5
+    v_length: u64,      // Video length in ms
+    start: u64,         // User start point in ms     Default: t= of the link or 0
+    u_length: u64,      // Clip length in ms          Default: 5s
     u_id: String,       // User Id
 }
 
+// Where the clip of a new intro comes from
+enum IntroSource {
+    Link(String, Option<u64>),          // Link and the timestamp in it
+    Upload(String, Vec<u8>, String),    // Filename, file and extension
+}
+
 #[derive(Debug)]
 pub struct User {}
 
@@ -104,85 +90,464 @@ impl TypeMapKey for User {
 }
 
 
+//--------------------------------------------------------------------------------------------------------------------------
+// Command Registry, every command is declared here and nowhere else
+
+pub const REGISTRY: &[CommandSpec] = &[
+    CommandSpec {
+        name: "help", aliases: &["commands"], prefixed: true,
+        usage: "Shows all commands or the usage of one command",
+        args: &[
+            arg("command", "Command to show", ArgKind::Text, Render::Positional),
+        ],
+        handler: Some(|i, a| Box::pin(helpCommand(i, a))),
+    },
+    CommandSpec {
+        name: "test", aliases: &[], prefixed: true,
+        usage: "Test command",
+        args: &[],
+        handler: Some(|_, _| Box::pin(async { println!("Test!"); Ok(()) })),
+    },
+    CommandSpec {
+        name: "setvideo", aliases: &["setintro"], prefixed: true,
+        usage: "Adds an intro from a link or audio file, without either it renders the intro again with new effects",
+        args: &[
+            arg("url", "Any yt-dlp supported link, or attach an audio file", ArgKind::Text, Render::Positional),
+            arg("start", "Start point, like 90 or 1:23.5, the t= of the link if not given", ArgKind::Timestamp, Render::Key),
+            range(arg("length", "Clip length in seconds, like 3.25", ArgKind::Timestamp, Render::Key), MIN_CLIP, MAX_CLIP),
+            arg("end", "End point, like 1:30, instead of a length", ArgKind::Timestamp, Render::Key),
+            fallback(arg("name", "Name of the intro, replaces the one with the same name", ArgKind::Text, Render::Key), "default"),
+            fallback(choices(arg("kind", "When the sound plays", ArgKind::Enum, Render::Key), &["intro", "outro", "move"]), "intro"),
+            range(arg("fadein", "Fade in length, like 0.5", ArgKind::Timestamp, Render::Key), 0, MAX_CLIP),
+            range(arg("fadeout", "Fade out length, like 1.5", ArgKind::Timestamp, Render::Key), 0, MAX_CLIP),
+            arg("speed", "Playback speed from 0.5 to 2", ArgKind::Float, Render::Key),
+            arg("pitch", "Pitch shift in semitones from -12 to 12", ArgKind::Float, Render::Key),
+            arg("bass", "Bass boost in dB from -20 to 20", ArgKind::Float, Render::Key),
+            arg("reverse", "Plays the clip backwards", ArgKind::Bool, Render::Switch),
+            arg("trimsilence", "Cuts silence at the start and the end", ArgKind::Bool, Render::Switch),
+            arg("status", "Shows how far your intro is", ArgKind::Bool, Render::Switch),
+            arg("cancel", "Stops your intro that is in the works", ArgKind::Bool, Render::Switch),
+        ],
+        handler: Some(|i, a| Box::pin(userMapCheckAndUpdate(i, a))),
+    },
+    CommandSpec {
+        name: "intro", aliases: &["intros"], prefixed: true,
+        usage: "Lists, removes, previews and picks between your intros, outros and move sounds",
+        args: &[
+            req(choices(arg("mode", "What to do, info shows where an intro came from and rollback brings back an older version", ArgKind::Enum, Render::Positional), &["list", "info", "history", "rollback", "remove", "select", "weight", "strategy", "preview", "file"])),
+            arg("name", "Intro name, or fixed|random|roundrobin|weighted (strategy)", ArgKind::Text, Render::Positional),
+            arg("version", "How many versions to go back, see history (rollback)", ArgKind::Int, Render::Positional),
+            range(arg("weight", "How often the intro comes up (weight)", ArgKind::Int, Render::Key), 1, 100),
+            arg("user", "Whose intro (file, info, list)", ArgKind::User, Render::Mention),
+            fallback(choices(arg("kind", "Which sounds", ArgKind::Enum, Render::Key), &["intro", "outro", "move"]), "intro"),
+        ],
+        handler: Some(|i, a| Box::pin(introHandler(i, a))),
+    },
+    CommandSpec {
+        name: "list", aliases: &[], prefixed: true,
+        usage: "Lists everyone with an intro on this server",
+        args: &[],
+        handler: Some(|i, _| Box::pin(listGuild(i))),
+    },
+    CommandSpec {
+        name: "disconnect", aliases: &[], prefixed: true,
+        usage: "Disconnects the bot from voice",
+        args: &[],
+        handler: Some(|i, _| Box::pin(disconnectVoice(i))),
+    },
+    CommandSpec {
+        name: "stfu", aliases: &[], prefixed: false,
+        usage: "Stops the current sound",
+        args: &[],
+        handler: Some(|i, _| Box::pin(stopSound(i))),
+    },
+    CommandSpec {
+        name: "volume", aliases: &["vol"], prefixed: true,
+        usage: "Shows or sets the volume sounds are played at on this server",
+        args: &[
+            range(arg("percent", "Volume in percent, shows the current one if left out", ArgKind::Int, Render::Positional), 0, 200),
+        ],
+        handler: Some(|i, a| Box::pin(volumeHandler(i, a))),
+    },
+    CommandSpec {
+        name: "sounds", aliases: &[], prefixed: true,
+        usage: "Shows or sets which sounds play on this server and how outros behave",
+        args: &[
+            choices(arg("kind", "Which sounds to turn on or off", ArgKind::Enum, Render::Positional), &["intro", "outro", "move"]),
+            arg("enabled", "on or off", ArgKind::Bool, Render::Positional),
+            range(arg("delay", "How long to wait before an outro, like 2.5", ArgKind::Timestamp, Render::Key), 0, 60),
+            arg("empty", "Play outros when nobody is left in the channel", ArgKind::Bool, Render::Key),
+        ],
+        handler: Some(|i, a| Box::pin(soundsHandler(i, a))),
+    },
+    CommandSpec {
+        name: "kys", aliases: &[], prefixed: false,
+        usage: "kys",
+        args: &[],
+        handler: None,
+    },
+    CommandSpec {
+        name: "timer", aliases: &[], prefixed: true,
+        usage: "Starts a timer and plays a sound in your voice channel when it ends",
+        args: &[
+            fallback(range(arg("minutes", "Minutes", ArgKind::Int, Render::Key), 0, 999), "0"),
+            fallback(range(arg("seconds", "Seconds", ArgKind::Int, Render::Key), 0, 59), "0"),
+        ],
+        handler: Some(|i, a| Box::pin(timer(i, a))),
+    },
+    CommandSpec {
+        name: "win", aliases: &[], prefixed: true,
+        usage: "W or L",
+        args: &[],
+        handler: Some(|i, _| Box::pin(winOrLose(i))),
+    },
+    CommandSpec {
+        name: "ban", aliases: &[], prefixed: true,
+        usage: "Bans a user",
+        args: &[
+            req(arg("user", "User to ban", ArgKind::User, Render::Mention)),
+        ],
+        handler: None,
+    },
+    CommandSpec {
+        name: "userlist", aliases: &[], prefixed: true,
+        usage: "Lists users",
+        args: &[],
+        handler: None,
+    },
+    CommandSpec {
+        name: "say", aliases: &[], prefixed: true,
+        usage: "Repeats your message and deletes the original",
+        args: &[
+            req(remainder(arg("text", "What to say", ArgKind::Text, Render::Positional))),
+        ],
+        handler: Some(|i, a| Box::pin(repeatMessage(i, a))),
+    },
+    CommandSpec {
+        name: "predict", aliases: &["prediction"], prefixed: true,
+        usage: "Lists, adds or removes predictions",
+        args: &[
+            req(choices(arg("mode", "What to do", ArgKind::Enum, Render::Flag), &["list", "add", "remove"])),
+            remainder(arg("text", "Prediction to add or ID to remove", ArgKind::Text, Render::Positional)),
+        ],
+        handler: Some(|i, a| Box::pin(addPrediction(i, a))),
+    },
+    CommandSpec {
+        name: "poll", aliases: &[], prefixed: true,
+        usage: "Starts a poll",
+        args: &[
+            remainder(arg("question", "Poll question", ArgKind::Text, Render::Positional)),
+        ],
+        handler: Some(|i, _| Box::pin(runPoll(i))),
+    },
+    CommandSpec {
+        name: "fn", aliases: &["fortnite"], prefixed: true,
+        usage: "Shows the new items in the Fortnite shop",
+        args: &[],
+        handler: Some(|i, _| Box::pin(fortniteWrapper(i))),
+    },
+    CommandSpec {
+        name: "event", aliases: &[], prefixed: true,
+        usage: "Creates, lists, deletes and subscribes to events in the bot channel",
+        args: &[
+            req(choices(arg("mode", "What to do", ArgKind::Enum, Render::Positional), &["create", "list", "delete", "subscribe", "unsubscribe", "channel"])),
+            arg("name", "Event name (create)", ArgKind::Text, Render::Bracketed),
+            arg("description", "Event message (create)", ArgKind::Text, Render::Bracketed),
+            arg("time", "Time as HH:MM (create)", ArgKind::Time, Render::Bracketed),
+            fallback(arg("subscribe", "Subscribe to the new event (create)", ArgKind::Bool, Render::Bracketed), "1"),
+            arg("command", "Command to run with the event (create)", ArgKind::Text, Render::Bracketed),
+            arg("date", "Date as DD/MM/YYYY (create)", ArgKind::Date, Render::Key),
+            fallback(choices(arg("interval", "How often the event repeats (create)", ArgKind::Enum, Render::Key), &["Daily", "Weekly", "Monthly", "Yearly", "Once"]), "Daily"),
+            arg("id", "Event ID (delete, subscribe, unsubscribe)", ArgKind::Int, Render::Key),
+        ],
+        handler: Some(|i, a| Box::pin(eventHandler(i, a))),
+    },
+    CommandSpec {
+        name: "santa", aliases: &[], prefixed: true,
+        usage: "Secret Santa, start sends everyone their giftee",
+        args: &[
+            choices(arg("mode", "What to do", ArgKind::Enum, Render::Positional), &["register", "remove", "start"]),
+        ],
+        handler: Some(|i, a| Box::pin(santaHandler(i, a))),
+    },
+    CommandSpec {
+        name: "language", aliases: &["sprache"], prefixed: true,
+        usage: "Sets the language the bot answers in on this server",
+        args: &[
+            req(choices(arg("language", "Language to use", ArgKind::Enum, Render::Positional), &["en", "de"])),
+        ],
+        handler: Some(|i, a| Box::pin(setLanguage(i, a))),
+    },
+];
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Finds a command by name or alias
+pub fn findCommand(name: &str) -> Option<&'static CommandSpec> {
+    let name: String = name.to_lowercase();
+
+    REGISTRY.iter().find(|c| c.name == name || c.aliases.contains(&name.as_str()))
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Checks message against the registry
+pub async fn checkCommand(content: &str) -> Option<&'static CommandSpec> {
+
+    let first: &str = content.split_whitespace().next()?;
+
+    match first.strip_prefix(PREFIX) {
+        Some(name) => findCommand(name).filter(|c| c.prefixed),
+        None => findCommand(first).filter(|c| !c.prefixed),
+    }
+} 
+
 
 //--------------------------------------------------------------------------------------------------------------------------
-// Checks message against list of commands
-pub async fn checkCommand(msg: &Message) -> COMMAND {
-    // Makes sure that the enum matches the consts
-    if CONSTS.len() > (COMMAND::COUNT - 1) {
-        println!("Refractor Enum or Consts!");
-        return COMMAND::INVALID;
+// Returns everything after the command name
+pub fn commandArgs(content: &str) -> String {
+
+    let content: &str = content.trim_start();
+
+    match content.find(char::is_whitespace) {
+        Some(pos) => content[pos..].trim_start().to_string(),
+        None => String::new(),
     }
+}
 
-    let mut command: COMMAND = COMMAND::INVALID;
-    let cmd_iter: COMMANDIter = COMMAND::iter(); // Creates iterator for enum, WHICH DOESN'T IMPLEMENT DEBUG BY THE WAY FOR SOME UNGODLY REASON
 
-    for (pos, c)  in CONSTS.iter().enumerate() {
-        
-        if msg.content.clone().starts_with(c) {
-                        
-            command = cmd_iter.get(pos).unwrap_or(COMMAND::INVALID);
+//--------------------------------------------------------------------------------------------------------------------------
+// Runs a command and replies with the error if it fails
+pub async fn executeCommand(cmd: &CommandSpec, inv: &Invocation) {
+
+    if let Err(E) = runCommand(cmd, inv).await {
+        reportError(cmd, E, inv).await;
+    }
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Parses, checks and runs the handler of a command
+async fn runCommand(cmd: &CommandSpec, inv: &Invocation) -> Result<(), RinrError> {
+
+    let handler: CommandFn = match cmd.handler {
+        Some(H) => H,
+        None => {
+            println!("Not Implemented Yet");
+            return Ok(());
+        },
+    };
+
+    let args: ParsedArgs = parseArgs(cmd.args, &commandArgs(&inv.content))?;
 
-            break;
+    checkPermission(cmd, &args, inv).await?;
+
+    // Kept until the handler is done so expensive commands count against the cap
+    let slot: Slot = acquireSlot(cmd, &args, inv).await?;
+
+    let result: Result<(), RinrError> = handler(inv, &args).await;
+
+    // A call the handler turned down doesn't count
+    if result.is_err() {
+        slot.refund();
+    }
+
+    result
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Logs the cause of an error and tells the user what went wrong
+async fn reportError(cmd: &CommandSpec, err: RinrError, inv: &Invocation) {
+
+    println!("{} by {} failed: {}", formatName(cmd), inv.author_name, err);
+
+    let lang: Lang = inv.lang().await;
+
+    let out: String = match &err {
+        RinrError::Parse(E) => tr(lang, "general.invalid_argument", &[("error", &E.render(lang)), ("usage", &formatSyntax(cmd))]),
+        _ => err.render(lang),
+    };
+
+    inv.say(out).await;
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Shows either all commands or the usage of a single one
+async fn helpCommand(inv: &Invocation, args: &ParsedArgs) -> Result<(), RinrError> {
+
+    let wanted: &str = args.text("command").unwrap_or("").trim_start_matches(PREFIX);
+    let lang: Lang = inv.lang().await;
+
+    if wanted.is_empty() {
+
+        let mut out: String = tr(lang, "help.title", &[]);
+        out.push('\n');
+
+        for c in REGISTRY {
+            let missing: String = if c.handler.is_none() {tr(lang, "help.not_implemented", &[])} else {String::new()};
+            out.push_str(&format!("`{}` - {}{}\n", formatName(c), describe(lang, c.name, c.usage), missing));
         }
+
+        out.push('\n');
+        out.push_str(&tr(lang, "help.footer", &[("prefix", &PREFIX.to_string())]));
+
+        inv.say(out).await;
+        return Ok(());
     }
 
-    command
-} 
+    match findCommand(wanted) {
+        Some(c) => inv.say(formatUsage(c, lang)).await,
+        None => inv.reply("help.unknown", &[("command", wanted)]).await,
+    }
+
+    Ok(())
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Formats the name of a command the way it has to be typed
+pub fn formatName(cmd: &CommandSpec) -> String {
+    if cmd.prefixed {
+        format!("{}{}", PREFIX, cmd.name)
+    } else {
+        cmd.name.to_uppercase()
+    }
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Generates the usage text of a command from its arguments
+pub fn formatUsage(cmd: &CommandSpec, lang: Lang) -> String {
+
+    let mut details: Vec<String> = vec![];
+
+    for a in cmd.args {
+
+        let mut detail: String = format!("`{}` ({:?}", a.name, a.kind);
+        if let Some((min, max)) = a.range {
+            detail.push_str(&format!(", {}-{}", min, max));
+        }
+        if let Some(default) = a.default {
+            detail.push_str(&format!(", {}", tr(lang, "help.default", &[("value", default)])));
+        }
+        detail.push_str(&format!(") - {}", describe(lang, &format!("{}.{}", cmd.name, a.name), a.description)));
+
+        details.push(detail);
+    }
+
+    let mut out: String = format!("**{}**\n{}\n`{}`", formatName(cmd), describe(lang, cmd.name, cmd.usage), formatSyntax(cmd));
+
+    if !cmd.aliases.is_empty() {
+        out.push('\n');
+        out.push_str(&tr(lang, "help.aliases", &[("aliases", &cmd.aliases.join(", "))]));
+    }
+
+    if !details.is_empty() {
+        out.push_str("\n\n");
+        out.push_str(&details.join("\n"));
+    }
+
+    out
+}
 
 
 //--------------------------------------------------------------------------------------------------------------------------
-// Matches commands with the functions they should execute
-pub async fn executeCommand(cmd: COMMAND, msg: &Message, ctx: &Context) {
-
-    match cmd {
-        COMMAND::E_TEST => println!("Test!"),
-        COMMAND::E_SET => userMapCheckAndUpdate(&msg, &ctx).await,
-        COMMAND::E_TIMER => timer(&msg, &ctx).await,
-        COMMAND::E_WIN => winOrLose(&msg, &ctx).await,
-        COMMAND::E_SAY => repeatMessage(&msg, &ctx).await,
-        COMMAND::E_PREDICTION => addPrediction(&msg, &ctx).await,
-        COMMAND::E_POLL => runPoll(&msg, &ctx).await,
-        COMMAND::E_FORTNITE => fortniteWrapper(&msg, &ctx).await,
-        COMMAND::E_EVENT => eventHandler(&msg, &ctx).await,
-        COMMAND::E_SANTA => santaHandler(&msg, &ctx).await,
-        COMMAND::INVALID => (),                                     // Should never happen 
-        _ => println!("Not Implemented Yet"),
+// Generates the syntax line of a command
+pub fn formatSyntax(cmd: &CommandSpec) -> String {
+
+    let mut syntax: Vec<String> = vec![formatName(cmd)];
+
+    for a in cmd.args {
+
+        let value: String = if a.choices.is_empty() {
+            format!("<{}>", a.name)
+        } else {
+            a.choices.join("|")
+        };
+
+        let part: String = match a.render {
+            Render::Positional => value,
+            Render::Flag => format!("-{}", value),
+            Render::Switch => a.name.to_string(),
+            Render::Key => format!("{}={}", a.name, value),
+            Render::Bracketed => format!("{}=[{}]", a.name, value),
+            Render::Mention => format!("@{}", a.name),
+        };
+
+        syntax.push(if a.required {part} else {format!("[{}]", part)});
     }
+
+    syntax.join(" ")
 }
 
+
 //--------------------------------------------------------------------------------------------------------------------------
 // gives a 50/50 chance for a win or a loss
-async fn winOrLose(msg: &Message, ctx: &Context) {
+async fn winOrLose(inv: &Invocation) -> Result<(), RinrError> {
     
     let rng: bool = rand::random();
     let out: &str = if rng {"W"} else {"L"};
 
-    say(msg, ctx, out.to_string()).await;
+    inv.say(out.to_string()).await;
 
+    Ok(())
 }
 
 //--------------------------------------------------------------------------------------------------------------------------
 // Repeats Message sent by user and deletes their message
-async fn repeatMessage(msg: &Message, ctx: &Context) {
+async fn repeatMessage(inv: &Invocation, args: &ParsedArgs) -> Result<(), RinrError> {
+
+    let out: String = args.text("text").unwrap_or("").to_string();
 
-    let out: String = msg.content.clone().replace(SAY, "");
+    inv.say(out).await;
 
-    say(msg, ctx, out).await;
+    inv.delete().await;
+
+    Ok(())
+}
 
-    delete(msg, ctx).await;
 
+//--------------------------------------------------------------------------------------------------------------------------
+// Sets the language of the guild the message was sent in, or the default one in DMs
+async fn setLanguage(inv: &Invocation, args: &ParsedArgs) -> Result<(), RinrError> {
+
+    let lang: Lang = match args.text("language").and_then(Lang::from_code) {
+        Some(L) => L,
+        None => return Ok(()),
+    };
+
+    let config: Arc<Mutex<RinrOptions>> = match inv.data.read().await.get::<RinrOptions>() {
+        Some(C) => C.clone(),
+        None => return Ok(()),
+    };
+
+    {
+        let mut config = config.lock().unwrap();
+
+        match inv.guild_id {
+            Some(G) => config.guilds.entry(G.get()).or_default().language = Some(lang),
+            None => config.language = lang,
+        }
+    }
+
+    saveConfig(&config).await?;
+
+    inv.say(tr(lang, "general.language_set", &[])).await;
+
+    Ok(())
 }
 
 
 //--------------------------------------------------------------------------------------------------------------------------
 // Handles download of a video from youtube
 #[cfg(feature = "old_downloader")]
-async fn rustDL(msg: &Message, ctx: &Context, vid: &mut VidInfo, yt: String, path: &Path) -> Result<(), Error> {
+async fn rustDL(inv: &Invocation, args: &ParsedArgs, vid: &mut VidInfo, link: String, path: &Path, _job: &JobHandle) -> Result<(), RinrError> {
 
-    let id = match Id::from_str(&yt) {  // Does it again, but this time its from the api
+    let id = match Id::from_raw(&link) {  // Only YouTube works here, the other sites need yt-dlp
         Ok(T) => T,
-        Err(_) => {errHandle(msg, ctx, 0).await; return Err(Error);},
+        Err(_) => return Err(RinrError::InvalidLink(link)),
     };
 
      // Starts a descrambler for the Video Data
@@ -191,7 +556,7 @@ async fn rustDL(msg: &Message, ctx: &Context, vid: &mut VidInfo, yt: String, pat
         .fetch()
         .await {
             Ok(T) => T,
-            Err(_) => {errHandle(msg, ctx, 0).await; return Err(Error);},
+            Err(E) => return Err(RinrError::Download(format!("{:?}", E))),
     };
 
     let info = descrambler.video_info();    // Saves video info in variable
@@ -199,12 +564,10 @@ async fn rustDL(msg: &Message, ctx: &Context, vid: &mut VidInfo, yt: String, pat
 
     // Saves some of the video info in a better format
     vid.name = info.player_response.video_details.title.clone();
-    vid.v_length = info.player_response.video_details.length_seconds.clone();
-    vid.start = matchStart(&msg.content.as_str(), &vid.v_length).await;
-    vid.u_length = matchLength(&msg.content.as_str(), &vid.v_length, &vid.start).await;
-    vid.u_id = msg.author.id.0.to_string().clone();
-
-    println!("{:#?}", &vid);
+    vid.v_length = info.player_response.video_details.length_seconds * 1000;
+    vid.start = matchStart(args.timestamp("start").unwrap_or(vid.start), &vid.v_length).await;
+    vid.u_length = matchLength(clipLength(args, vid.start)?, &vid.v_length, &vid.start).await;
+    vid.u_id = inv.author.0.to_string().clone();
 
 
     // Tries to download video to location
@@ -216,161 +579,400 @@ async fn rustDL(msg: &Message, ctx: &Context, vid: &mut VidInfo, yt: String, pat
         .download_to(path)
         .await {
             Ok(()) => println!("Successful Download!"),
-            Err(_) => {errHandle(msg, ctx, 1).await; return Err(Error);}
+            Err(E) => return Err(RinrError::Download(format!("{:?}", E))),
         };
 
     // Tries to trim the video
-    match editVideo(&vid).await {
-        Ok(()) => println!("Successful Edit!"),
-        Err(_) => {errHandle(msg, ctx, 2).await; return Err(Error);},
-    };  
+    editVideo(&vid).await?;
+    println!("Successful Edit!");
 
     Ok(())
 }
 
-#[cfg(not(feature = "old_downloader"))]
-async fn updateInfo(vid: &mut VidInfo, msg: &Message) {
+async fn updateInfo(vid: &mut VidInfo, inv: &Invocation, args: &ParsedArgs) -> Result<(), RinrError> {
 
     // Saves some of the video info in a better format
-    vid.start = matchStart(&msg.content.as_str(), &vid.v_length).await;
-    vid.u_length = matchLength(&msg.content.as_str(), &vid.v_length, &vid.start).await;
-    vid.u_id = msg.author.id.get().to_string().clone();
-
-    println!("{:#?}", &vid);
+    vid.start = matchStart(args.timestamp("start").unwrap_or(vid.start), &vid.v_length).await;
+    vid.u_length = matchLength(clipLength(args, vid.start)?, &vid.v_length, &vid.start).await;
+    vid.u_id = inv.author.get().to_string().clone();
 
+    Ok(())
 } 
 
 
+//--------------------------------------------------------------------------------------------------------------------------
+// Gets the requested clip length in ms from either length or end
+fn clipLength(args: &ParsedArgs, start: u64) -> Result<u64, RinrError> {
+
+    let length: u64 = match (args.timestamp("length"), args.timestamp("end")) {
+        (Some(_), Some(_)) => return Err(ArgError::new("end", ArgReason::Conflict("length".to_string())).into()),
+        (Some(L), None) => return Ok(L),    // Range is checked by the parser
+        (None, Some(E)) if E > start => E - start,
+        (None, Some(E)) => return Err(ArgError::invalid("end", &formatTimestamp(E), Expected::After(formatTimestamp(start))).into()),
+        (None, None) => return Ok(DEFAULT_CLIP * 1000),
+    };
+
+    if !(MIN_CLIP * 1000..=MAX_CLIP * 1000).contains(&length) {
+        return Err(ArgError::new("length", ArgReason::Range(MIN_CLIP, MAX_CLIP)).into());
+    }
+
+    Ok(length)
+}
+
+
 #[cfg(not(feature = "old_downloader"))]
-async fn rustDL(msg: &Message, ctx: &Context, vid: &mut VidInfo, yt: String, path: &Path) -> Result<(), Error> {
+async fn rustDL(inv: &Invocation, args: &ParsedArgs, vid: &mut VidInfo, link: String, path: &Path, job: &JobHandle) -> Result<(), RinrError> {
 
-    let form: String = format!("https://www.youtube.com/watch?v={}", yt);
+    let (title, duration): (Option<String>, Option<u64>) = fetchInfo(&link, &job.cancel).await?;
 
-    println!("{}", form);
+    // Not every site has titles, the link does the job as well
+    vid.name = title.unwrap_or(link.clone());
+    vid.v_length = match duration {
+        Some(D) => D,
+        None => return Err(RinrError::InvalidLink(link)),     // Livestreams have no duration
+    };
 
-    let video = match YoutubeDl::new(form).run() {
-        Ok(T) => T,
-        Err(E) => {
-            println!("{}", E);
-            return  Err(Error);
-        },  
+    updateInfo(vid, inv, args).await?;
+
+    downloadSection(&link, vid.start, vid.start + vid.u_length, path.to_str().unwrap(), &job.cancel, &|p| job.set(JobStatus::Downloading(Some(p)))).await?;
+    println!("Successful Download!");
+
+    Ok(())
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Checks that a link can be handed to yt-dlp and reads the t= or start= timestamp out of it
+fn parseLink(raw: &str) -> Result<(String, Option<u64>), RinrError> {
+
+    // Discord users wrap links in <> to hide the embed
+    let trimmed: &str = raw.trim().trim_start_matches('<').trim_end_matches('>');
+
+    let link: reqwest::Url = match reqwest::Url::parse(trimmed) {
+        Ok(L) if matches!(L.scheme(), "http" | "https") && L.host_str().is_some() => L,
+        _ => return Err(RinrError::InvalidLink(raw.to_string())),
     };
 
-    match video {
-        youtube_dl::YoutubeDlOutput::SingleVideo(V) => {
-            vid.name = match V.title {
-                Some(T) => T,
-                None => {
-                        errHandle(msg, ctx, 0).await; 
-                        return Err(Error);
-                },
-            };
-            vid.v_length = match V.duration {
-                Some(T) => T.as_i64().unwrap() as u64,
-                None => {
-                    errHandle(msg, ctx, 0).await; 
-                    return Err(Error);
-                },
-            };            
-        },
-        _ => {
-            errHandle(msg, ctx, 0).await; 
-            return Err(Error);
-        },
+    // youtu.be/..?t=90, watch?v=..&t=1m30s, #t=90 or start=90 on embeds
+    let query: Option<String> = link.query_pairs()
+                                    .find(|(k, _)| k == "t" || k == "start")
+                                    .map(|(_, v)| v.into_owned());
+
+    let fragment: Option<String> = link.fragment()
+                                       .and_then(|f| f.strip_prefix("t="))
+                                       .map(|f| f.to_string());
+
+    let start: Option<u64> = query.or(fragment).and_then(|t| parseTimestamp(&t));
+
+    Ok((link.to_string(), start))
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Downloads an attached file after checking its type and size, returns the file and its extension
+async fn fetchAttachment(attachment: &AttachmentInfo) -> Result<(Vec<u8>, String), RinrError> {
+
+    let max_bytes: u64 = MAX_UPLOAD_MB * 1024 * 1024;
+
+    let ext: String = Path::new(&attachment.filename).extension()
+                                                     .and_then(|e| e.to_str())
+                                                     .unwrap_or("")
+                                                     .to_lowercase();
+
+    if !UPLOAD_TYPES.contains(&ext.as_str()) {
+        return Err(RinrError::InvalidUpload(format!("{} is not a supported file type", attachment.filename)));
     }
 
-    updateInfo(vid, &msg).await;
+    if attachment.size > max_bytes {
+        return Err(RinrError::InvalidUpload(format!("{} has {} bytes", attachment.filename, attachment.size)));
+    }
 
+    let response = match reqwest::get(&attachment.url).await.and_then(|r| r.error_for_status()) {
+        Ok(R) => R,
+        Err(E) => return Err(RinrError::Download(format!("Unable to fetch {}: {}", attachment.url, E))),
+    };
+
+    let upload: Vec<u8> = match response.bytes().await {
+        Ok(B) => B.to_vec(),
+        Err(E) => return Err(RinrError::Download(format!("Unable to fetch {}: {}", attachment.url, E))),
+    };
 
-    let section: String = format!("*{}-{}", formatSec(vid.start), formatSec(vid.start + vid.u_length));
+    // The reported size can't be trusted for files that don't come from discord
+    if upload.len() as u64 > max_bytes {
+        return Err(RinrError::InvalidUpload(format!("{} has {} bytes", attachment.filename, upload.len())));
+    }
 
-    let command: String = format!(
-        "yt-dlp -o {} --download-sections {} -x --audio-format mp3 -f bestaudio https://www.youtube.com/watch?v={} --force-overwrites --force-keyframes-at-cuts",
-        path.to_str().unwrap(), section, yt
-    );
+    Ok((upload, ext))
+}
 
-    println!("{}", command);
 
-    let down_res: Result<Result<std::process::Output, std::io::Error>, task::JoinError> = task::spawn_blocking(move || {
-        Command::new("nu")
-            .arg("-c")
-            .arg(command)
-            .output()  
-    }).await;
+//--------------------------------------------------------------------------------------------------------------------------
+// Trims an uploaded file like a YouTube video and turns it into an mp3
+async fn attachmentDL(inv: &Invocation, args: &ParsedArgs, vid: &mut VidInfo, upload: Vec<u8>, ext: &str, path: &Path, cancel: &CancelToken) -> Result<(), RinrError> {
 
-    match down_res {
-        Ok(T) => {
-            match T {
-                Ok(_) => println!("Successful Download!"),
-                Err(_) => {
-                    println!("Output Error!");
-                    errHandle(msg, ctx, 1).await; 
-                    return Err(Error);
-                },
-            }
-        },
-        Err(_) => {
-            println!("Join Error!");
-            errHandle(msg, ctx, 1).await; 
-            return Err(Error);
-        },    
+    let target: String = path.to_str().unwrap().to_string();
+    let source: String = target.replace(".mp3", &format!("_upload.{}", ext));
+
+    tokio::fs::write(&source, upload).await?;
+
+    let res: Result<(), RinrError> = transcodeUpload(inv, args, vid, &source, &target, cancel).await;
+
+    if let Err(E) = tokio::fs::remove_file(&source).await {
+        println!("Unable to delete upload {}: {}", source, E);
+    }
+
+    res
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Checks the length of an upload and cuts the requested part out of it
+async fn transcodeUpload(inv: &Invocation, args: &ParsedArgs, vid: &mut VidInfo, source: &str, target: &str, cancel: &CancelToken) -> Result<(), RinrError> {
+
+    vid.v_length = probeDuration(source, cancel).await?;
+
+    if vid.v_length == 0 || vid.v_length > MAX_UPLOAD_MINUTES * 60_000 {
+        return Err(RinrError::InvalidUpload(format!("{} is {} long", vid.name, formatTimestamp(vid.v_length))));
     }
 
+    updateInfo(vid, inv, args).await?;
+
+    cutAudio(source, target, vid.start, vid.u_length, cancel).await?;
+    println!("Successful Edit!");
 
     Ok(())
 }
 
 
+//--------------------------------------------------------------------------------------------------------------------------
+// Checks a $setvideo and starts a job for it, status and cancel look at the job that is running
+async fn userMapCheckAndUpdate(inv: &Invocation, args: &ParsedArgs) -> Result<(), RinrError> {
+
+    if args.bool("status").unwrap_or(false) {
+        return jobStatus(inv).await;
+    }
+
+    if args.bool("cancel").unwrap_or(false) {
+        return cancelJob(inv).await;
+    }
+
+    let name: String = checkName(args.text("name").unwrap_or("default"))?;
+    let kind: SoundKind = soundKind(args);
+    let effects: String = effectChain(args)?;
+
+    // Checked before anything is fetched or deleted, startJob checks again in case two came in at once
+    checkRunning(inv).await?;
+
+    let attachment: Option<AttachmentInfo> = inv.attachments.first().cloned();
+
+    // Without a link or a file the intro is only rendered again with the new effects
+    if args.text("url").is_none() && attachment.is_none() {
+        return startJob(inv, name.clone(), move |inv, job| async move {
+            rerenderIntro(&inv, &job, kind, &name, effects).await
+        }).await;
+    }
+
+    // Replacing an intro is always fine, adding one only while there is room
+    let full: bool = {
+        let mut u_data = inv.data.write().await;
+        let intros = u_data.get_mut::<IntroLibrary>().unwrap().entry(kind, inv.author.get());
+
+        intros.find(&name).is_none() && intros.sounds.len() >= MAX_INTROS
+    };
+
+    if full {
+        return Err(RinrError::TooManyIntros);
+    }
+
+    // A link wins over an attachment, attachments are gone once the message is deleted so they are fetched first
+    let source: IntroSource = match (args.text("url"), attachment) {
+        (None, Some(A)) => {
+            let (upload, ext): (Vec<u8>, String) = fetchAttachment(&A).await?;
+            IntroSource::Upload(A.filename, upload, ext)
+        },
+        (url, _) => {
+            let (link, start): (String, Option<u64>) = parseLink(url.unwrap_or(""))?;
+            IntroSource::Link(link, start)
+        },
+    };
+
+    inv.delete().await;
+
+    let args: ParsedArgs = args.clone();
+
+    startJob(inv, name.clone(), move |inv, job| async move {
+        ingestIntro(&inv, &args, &job, kind, name, effects, source).await
+    }).await
+}
 
 
 //--------------------------------------------------------------------------------------------------------------------------
-// Handles most of the logic for the YouTube video detection 
-async fn userMapCheckAndUpdate(msg: &Message, ctx: &Context) {
+// Downloads or cuts the clip of an intro, renders it and saves it as its newest version, runs as a job
+// Everything goes to files of the new version, so the intro that is playing stays as it is if something fails
+async fn ingestIntro(inv: &Invocation, args: &ParsedArgs, job: &JobHandle, kind: SoundKind, name: String, effects: String, source: IntroSource) -> Result<String, RinrError> {
+
+    let version: u32 = {
+        let mut u_data = inv.data.write().await;
+        u_data.get_mut::<IntroLibrary>().unwrap().entry(kind, inv.author.get()).find(&name).map(|s| s.nextVersion()).unwrap_or(0)
+    };
 
-    say(msg, ctx, "Aight".to_string()).await;
+    let file: String = versionFile(&introFile(kind, inv.author.get(), &name), version);
 
-    delete(msg, ctx).await;
+    // Only the files of the new version are touched until the library points to them
+    let fetched: Result<(VidInfo, String), RinrError> = async {
 
-    // Sets filepath
-    let u_name: String = removeUserAt(msg.author.id.get().to_string());
-    let path: &Path = Path::new(u_name.as_str());
+        // Sets filepath, the download goes to the source the intro is rendered from
+        let source_file: String = removeUserAt(sourceFile(&file));
+        let path: &Path = Path::new(source_file.as_str());
 
-    println!("Path to File: {:?}", path);
+        let mut vid: VidInfo = VidInfo {name: "".to_string(), v_length: 0, start: 0, u_length: 0, u_id: "".to_string(),};
+
+        // Kept with the intro so $intro info can show where it came from
+        let origin: String = match source {
+            IntroSource::Upload(F, upload, ext) => {
+                job.set(JobStatus::Trimming);
+
+                vid.name = F.clone();
+                attachmentDL(inv, args, &mut vid, upload, &ext, path, &job.cancel).await?;
+                F
+            },
+            IntroSource::Link(L, start) => {
+                job.set(JobStatus::Downloading(None));
+
+                // The start argument still wins over the timestamp in the link
+                vid.start = start.unwrap_or(0);
+
+                rustDL(inv, args, &mut vid, L.clone(), path, job).await?;
+                L
+            },
+        };
 
-    // Sets and matches YouTube Regex
-    let reg: Regex = Regex::new(r"https://(?:www)?\.?youtu\.?be\.?(?:com)?/?(?:watch\?v=)?(.{11})").unwrap();   // Regex to match YouTube links (long and short urls work / YouTube Shorts don't)
-    let mut yt: String = msg.content.clone();
-    let mut vid: VidInfo = VidInfo {name: "".to_string(), v_length: 0, start: 0, u_length: 0, u_id: "".to_string(),};
+        job.set(JobStatus::Rendering);
 
-    match reg.captures(&yt) {   // Uses Regex to capture the 11 URL characters that are important
-        Some(capture) => yt = capture.get(1).unwrap().as_str().to_string().to_owned(),  
-        None => {errHandle(msg, ctx, 0).await; return;},
+        renderIntro(source_file.clone(), removeUserAt(file.clone()), &effects, &loudnessOptions(inv).await, &job.cancel).await?;
+        println!("Successful Render!");
+
+        Ok((vid, origin))
+    }.await;
+
+    let (vid, origin): (VidInfo, String) = match fetched {
+        Ok(F) => F,
+        Err(E) => {
+            discardVersion(&file).await;
+            return Err(E);
+        },
+    };
+
+    let info: IntroInfo = IntroInfo {
+        source: origin,
+        title: vid.name.clone(),
+        start: vid.start,
+        length: vid.u_length,
+        duration: vid.v_length,
+        set_by: inv.author.get(),
+        set_at: clock::now(&inv.data).await,
+    };
+
+    let (dropped, snapshot): (Vec<IntroVersion>, IntroLibrary) = {
+        let mut u_data = inv.data.write().await;
+        let library: &mut IntroLibrary = u_data.get_mut::<IntroLibrary>().unwrap();
+
+        let sound: IntroSound = IntroSound { name: name.clone(), file, weight: 1, effects, info: Some(info), version, history: vec![] };
+
+        (library.entry(kind, inv.author.get()).add(sound), library.clone())
+    };
+
+    writeIntros(&snapshot).await?;
+
+    for old in dropped {
+        deleteSoundFiles(&old.file);
     }
 
-    match rustDL(msg, ctx, &mut vid, yt, path).await {
-        Ok(()) => (),
-        Err(_) => return,
+    Ok(tr(inv.lang().await, "intro.added", &[
+        ("name", &name),
+        ("start", &formatTimestamp(vid.start)),
+        ("end", &formatTimestamp(vid.start + vid.u_length)),
+        ("user", inv.author_name.as_str()),
+    ]))
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Renders an existing intro again from its source with other effects as a new version, runs as a job
+async fn rerenderIntro(inv: &Invocation, job: &JobHandle, kind: SoundKind, name: &str, effects: String) -> Result<String, RinrError> {
+
+    let current: Option<IntroSound> = {
+        let mut u_data = inv.data.write().await;
+        u_data.get_mut::<IntroLibrary>().unwrap().entry(kind, inv.author.get()).find(name).cloned()
+    };
+
+    // Intros from before sources were kept can only be replaced
+    let (current, source): (IntroSound, String) = match current.map(|s| { let source: String = removeUserAt(sourceFile(&s.file)); (s, source) }) {
+        Some((C, S)) if checkVidPath(&S) => (C, S),
+        _ => return Err(ArgError::new("url", ArgReason::Required).into()),
+    };
+
+    let version: u32 = current.nextVersion();
+    let file: String = versionFile(&introFile(kind, inv.author.get(), name), version);
+
+    job.set(JobStatus::Rendering);
+
+    // The clip is copied so every version can be rendered again on its own
+    let render_res: Result<(), RinrError> = async {
+        tokio::fs::copy(&source, removeUserAt(sourceFile(&file))).await?;
+        renderIntro(source.clone(), removeUserAt(file.clone()), &effects, &loudnessOptions(inv).await, &job.cancel).await
+    }.await;
+
+    if let Err(E) = render_res {
+        discardVersion(&file).await;
+        return Err(E);
+    }
+
+    let (dropped, snapshot): (Vec<IntroVersion>, IntroLibrary) = {
+        let mut u_data = inv.data.write().await;
+        let library: &mut IntroLibrary = u_data.get_mut::<IntroLibrary>().unwrap();
+
+        let dropped: Vec<IntroVersion> = match library.entry(kind, inv.author.get()).sounds.iter_mut().find(|s| s.name == name) {
+            Some(S) => {
+                let newer: IntroVersion = IntroVersion { file, effects, info: S.info.clone(), version };
+                S.supersede(newer)
+            },
+            None => vec![],
+        };
+
+        (dropped, library.clone())
+    };
+
+    writeIntros(&snapshot).await?;
+
+    for old in dropped {
+        deleteSoundFiles(&old.file);
     }
-       
-    let mut response: String = SET_RESPONSE.to_string();
-    response.push_str(msg.author.name.as_str());
+
+    Ok(tr(inv.lang().await, "intro.rendered", &[("name", name)]))
+}
 
 
-    say(msg, ctx, response).await;
+//--------------------------------------------------------------------------------------------------------------------------
+// Deletes what a failed or cancelled job left of a new version, including a download that didn't finish
+async fn discardVersion(file: &str) {
+
+    deleteSoundFiles(file);
 
+    let _ = tokio::fs::remove_file(removeUserAt(sourceFile(file)).replace(".mp3", ".download.mp3")).await;
 }
 
 
 //--------------------------------------------------------------------------------------------------------------------------
 // Trims the video file
 #[cfg(feature = "old_downloader")]
-async fn editVideo(vid: &VidInfo) -> Result<(), Error> {
+async fn editVideo(vid: &VidInfo) -> Result<(), RinrError> {
 
     let path: String = removeUserAt(vid.u_id.clone());
     let path_edit: String = path.clone().replace(".mp3", "_edit.mp3");
 
-    let start: String = hrtime::from_sec_padded(vid.start);
-    let end: String = hrtime::from_sec_padded(vid.start + vid.u_length);
+    let start: String = formatTimestamp(vid.start);
+    let end: String = formatTimestamp(vid.start + vid.u_length);
     
     match fs::remove_file(&path_edit) {
         Ok(()) => (),
@@ -385,7 +987,7 @@ async fn editVideo(vid: &VidInfo) -> Result<(), Error> {
             }
             println!("Stdout: {:?}", O.stdout);
         }
-        Err(_) => return Err(Error), 
+        Err(E) => return Err(RinrError::Download(format!("Unable to trim video, old video has been overwritten: {}", E))), 
     };
     
 
@@ -395,79 +997,287 @@ async fn editVideo(vid: &VidInfo) -> Result<(), Error> {
 
 
 //--------------------------------------------------------------------------------------------------------------------------
-// Error Handler 
-async fn errHandle(msg: &Message, ctx: &Context, case: u8) {
+// Fits the requested start time into the video
+async fn matchStart(start: u64, v_length: &u64) -> u64 {
 
-     let err: &str = match case {
+    if start >= *v_length {
+        v_length.saturating_sub(2000)   // Checks if custom start is longer than video len, if yes assigns it to max vid length - 2s
+    } else {
+        start
+    }
+}
 
-        0 => "No valid Youtube Link given!",
-        1 => "Unable to download Video!",
-        2 => "Unable to edit video, old video has been overwritten!",
-        _ => "Invalid Case! / Not Implemented!",
-    };
 
-    say(msg, ctx, err.to_string()).await;
+//--------------------------------------------------------------------------------------------------------------------------
+// Fits the requested clip length into the video, the range itself is already checked by the parser
+async fn matchLength(length: u64, v_length: &u64, v_start: &u64) -> u64 {
 
+    if length + v_start >= *v_length {
+        v_length - v_start  // Checks if the custom length would go over the video length and, if appropriate, sets it to the remaining time in the video
+    } else {
+        length
+    }
 }
 
 
-//--------------------------------------------------------------------------------------------------------------------------
-// Matches custom start time for a youtube video
-async fn matchStart(msg: &str, v_length: &u64) -> u64 {
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::{self, Receiver, Sender};
+    use std::time::Duration;
+    use chrono::{NaiveDate, NaiveTime};
+    use serenity::all::{ChannelId, GuildId, Permissions, UserId};
+    use serenity::prelude::{RwLock, TypeMap};
+    use crate::clock::{Clock, FakeClock};
+    use crate::gateway::{Gateway, MemberInfo, MemoryGateway, Outgoing};
 
-    let start: Regex = Regex::new(r"start=([0-9]+)").unwrap();              // Optional Regex to match the start point
+    #[tokio::test]
+    async fn failedIngestKeepsTheActiveIntro() {
 
-    let vid_start: u64 = match start.captures(&msg) {   
-        Some(capture) => {
-            if capture.get(1).unwrap().as_str().to_string().to_owned().clone().parse::<u64>().unwrap() >=  *v_length {
-                v_length - 2   // Checks if custom start is longer than video len, if yes assigns it to max vid length - 2
-            } else {
-                capture.get(1).unwrap().as_str().to_string().to_owned().parse::<u64>().unwrap()
-            }
-        },  
-        None => 0,  // No match == start at 0
-    };
+        let gateway: Arc<MemoryGateway> = Arc::new(MemoryGateway::default());
+        let data: Arc<RwLock<TypeMap>> = Arc::new(RwLock::new(TypeMap::new()));
+        let inv: Invocation = gateway.invocation(data.clone(), 1, 10, "$setvideo hi https://example.com/fail");
 
-    vid_start
-}
+        let active: IntroSound = IntroSound { name: "hi".to_string(), file: introFile(SoundKind::Intro, 10, "hi"), weight: 7, effects: "bass".to_string(), info: None, version: 0, history: vec![] };
 
+        let mut library: IntroLibrary = IntroLibrary::default();
+        library.entry(SoundKind::Intro, 10).add(active.clone());
+        data.write().await.insert::<IntroLibrary>(library);
 
-//--------------------------------------------------------------------------------------------------------------------------
-// Matches custom length for a youtube video
-async fn matchLength(msg: &str, v_length: &u64, v_start: &u64) -> u64 {
-    
-    let length: Regex =  Regex::new(r"length=([0-9]{1,2})").unwrap();       // Optional Regex to match the clip length
-    
-    let u_length: u64 = match length.captures(&msg) {   
-        Some(capture) => {
-            let mut len: u64 = capture.get(1)
-                                      .unwrap()
-                                      .as_str()
-                                      .to_string()
-                                      .to_owned()
-                                      .clone()
-                                      .parse::<u64>()
-                                      .unwrap();
-
-            if  len > 10 { len = 10; }           // Makes sure length is in the right size bracket 
-            else if len < 1 {len = 1; }
-
-            if  len + v_start >= *v_length {
-                len = v_length - v_start;  // Checks if the custom length would go over the video length and, if appropriate, sets it to the remaining time in the video
-            } 
-
-            len
-        },  
-        None => {
-            let mut len: u64 = 5;   // Default length of 5
+        // Cancelled from the start, so the download stops before anything is fetched
+        let job: JobHandle = JobHandle::detached();
+        job.cancel.cancel();
+
+        let source: IntroSource = IntroSource::Link("https://example.com/fail".to_string(), None);
+        assert!(ingestIntro(&inv, &ParsedArgs::default(), &job, SoundKind::Intro, "hi".to_string(), "echo".to_string(), source).await.is_err());
+
+        let mut u_data = data.write().await;
+        let current: &IntroSound = u_data.get_mut::<IntroLibrary>().unwrap().entry(SoundKind::Intro, 10).find("hi").unwrap();
+
+        assert_eq!((current.file.as_str(), current.weight, current.effects.as_str(), current.version), (active.file.as_str(), 7, "bass", 0));
+        assert!(current.history.is_empty());
+        assert!(!checkVidPath(&removeUserAt(versionFile(&active.file, 1))));
+    }
+
+    #[test]
+    fn linksGiveTheirStart() {
+
+        let start = |raw: &str| parseLink(raw).unwrap().1;
+
+        assert_eq!(start("https://youtu.be/abc?t=90"), Some(90_000));
+        assert_eq!(start("https://www.youtube.com/watch?v=abc&t=1m30s"), Some(90_000));
+        assert_eq!(start("https://www.youtube.com/embed/abc?start=45"), Some(45_000));
+        assert_eq!(start("https://vimeo.com/123#t=2m5s"), Some(125_000));
+        assert_eq!(start("<https://youtu.be/abc?t=12>"), Some(12_000));
+        assert_eq!(start("https://youtu.be/abc"), None);
+        assert_eq!(start("https://youtu.be/abc?t=soon"), None);
+
+        assert_eq!(parseLink("<https://youtu.be/abc?t=12>").unwrap().0, "https://youtu.be/abc?t=12");
+        assert!(matches!(parseLink("ftp://example.com/a.mp3"), Err(RinrError::InvalidLink(_))));
+        assert!(matches!(parseLink("not a link"), Err(RinrError::InvalidLink(_))));
+    }
+
+    #[tokio::test]
+    async fn attachmentsAreCheckedBeforeTheDownload() {
+
+        // Nothing listens there, a check that let it through would fail with Download instead
+        let attachment = |filename: &str, size: u64| AttachmentInfo { url: "http://127.0.0.1:9/upload".to_string(), filename: filename.to_string(), size };
+        let max_bytes: u64 = MAX_UPLOAD_MB * 1024 * 1024;
+
+        for (filename, size) in [("clip.mp4", 1024), ("notes.txt", 1024), ("intro", 1024), ("intro.mp3", max_bytes + 1), ("INTRO.OGG", max_bytes * 2)] {
+            assert!(matches!(fetchAttachment(&attachment(filename, size)).await, Err(RinrError::InvalidUpload(_))), "{}", filename);
+        }
+
+        // Upper case extensions and the exact limit pass the checks
+        for (filename, size) in [("intro.MP3", max_bytes), ("intro.webm", 0)] {
+            assert!(matches!(fetchAttachment(&attachment(filename, size)).await, Err(RinrError::Download(_))), "{}", filename);
+        }
+    }
+
+    const CHANNEL: ChannelId = ChannelId::new(1);
+
+    // Shared data like the bot sets it up, without the files
+    fn state() -> Arc<RwLock<TypeMap>> {
+
+        let mut data: TypeMap = TypeMap::new();
+        data.insert::<RinrOptions>(Arc::new(Mutex::new(RinrOptions::default())));
+        data.insert::<User>(HashMap::new());
+        data.insert::<Santa>(Santa::default());
+        Arc::new(RwLock::new(data))
+    }
+
+    // Runs a message of a user through the dispatcher and takes everything the bot did
+    async fn run(gateway: &Arc<MemoryGateway>, data: &Arc<RwLock<TypeMap>>, author: u64, content: &str) -> Vec<Outgoing> {
+
+        let cmd: &CommandSpec = checkCommand(content).await.unwrap();
+        executeCommand(cmd, &gateway.invocation(data.clone(), 1, author, content)).await;
+
+        gateway.take()
+    }
+
+    fn said(key: &str, vars: &[(&str, &str)]) -> Outgoing {
+        Outgoing::Message(CHANNEL, tr(Lang::En, key, vars))
+    }
+
+    fn member(gateway: &MemoryGateway, user: u64, permissions: Permissions) {
+        gateway.members.lock().unwrap().insert((GuildId::new(1), UserId::new(user)), MemberInfo { name: format!("user{}", user), roles: vec![], permissions });
+    }
+
+    // Runs a message the event thread answers, its answer can come before or after the handler returns
+    async fn ask(gateway: &Arc<MemoryGateway>, data: &Arc<RwLock<TypeMap>>, author: u64, content: &str) -> Vec<Outgoing> {
+
+        let out: Vec<Outgoing> = run(gateway, data, author, content).await;
+
+        if out.is_empty() {
+            return nextSent(gateway).await;
+        }
 
-            if v_start + len > *v_length {
-                len = v_length - v_start;
+        out
+    }
+
+    // Waits for the event thread to send something
+    async fn nextSent(gateway: &MemoryGateway) -> Vec<Outgoing> {
+
+        for _ in 0..500 {
+            let out: Vec<Outgoing> = gateway.take();
+
+            if !out.is_empty() {
+                return out;
             }
 
-            len
+            tokio::time::sleep(Duration::from_millis(10)).await;
         }
-    };
 
-    u_length
+        vec![]
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn timerRemindsWhenItEnds() {
+
+        let gateway: Arc<MemoryGateway> = Arc::new(MemoryGateway::default());
+        let data: Arc<RwLock<TypeMap>> = state();
+
+        assert_eq!(run(&gateway, &data, 10, "$timer minutes=1 seconds=30").await, vec![
+            said("timer.set", &[("minutes", "1"), ("seconds", "30")]),
+            said("timer.ended", &[("user", "10")]),
+        ]);
+
+        assert_eq!(run(&gateway, &data, 10, "$timer").await, vec![said("timer.invalid", &[])]);
+
+        let out: Vec<Outgoing> = run(&gateway, &data, 10, "$timer seconds=75").await;
+        assert!(matches!(&out[..], [Outgoing::Message(_, M)] if M.contains("`seconds`: has to be between 0 and 59")));
+    }
+
+    #[tokio::test]
+    async fn eventCommandsGoThroughTheEventThread() {
+
+        let gateway: Arc<MemoryGateway> = Arc::new(MemoryGateway::default());
+        let data: Arc<RwLock<TypeMap>> = state();
+        let shared: Arc<Mutex<RinrOptions>> = data.read().await.get::<RinrOptions>().unwrap().clone();
+
+        let clock: Arc<FakeClock> = Arc::new(FakeClock::new(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap().and_hms_opt(12, 0, 0).unwrap()));
+        let (send, recv): (Sender<EventSignal>, Receiver<EventSignal>) = mpsc::channel();
+        data.write().await.insert::<DailyEventSignalKey>(send);
+
+        let thread_gateway: Arc<dyn Gateway> = gateway.clone();
+        let thread_clock: Arc<dyn Clock> = clock.clone();
+        let thread_shared: Arc<Mutex<RinrOptions>> = shared.clone();
+        let thread: std::thread::JoinHandle<()> = std::thread::spawn(move || loops(thread_shared, recv, thread_gateway, thread_clock));
+
+        assert_eq!(ask(&gateway, &data, 10, "$event list").await, vec![said("event.no_channel", &[])]);
+
+        // Missing ids are caught before anything reaches the thread
+        let out: Vec<Outgoing> = run(&gateway, &data, 10, "$event subscribe").await;
+        assert!(matches!(&out[..], [Outgoing::Message(_, M)] if M.contains("`id`: is required for subscribe")));
+
+        {
+            let mut config = shared.lock().unwrap();
+            let mut config: &mut RinrOptions = &mut config;
+            config.setChannel(5, Some(1));
+            config.insert(DailyEvent {
+                name: "Standup".to_string(),
+                id: 0,
+                message: Some("Daily sync".to_string()),
+                timestamp: NaiveTime::from_hms_opt(12, 1, 0).unwrap(),
+                subscribers: vec![UserId::new(10)],
+                command: None,
+                date: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+                interval: Timeslice::Daily,
+            });
+        }
+
+        assert!(matches!(&ask(&gateway, &data, 10, "$event list").await[..], [Outgoing::Message(C, M)] if *C == ChannelId::new(5) && M.contains("Event: Standup")));
+
+        // The event is posted in the bot channel once it is due
+        clock.advance(60);
+        assert_eq!(nextSent(&gateway).await, vec![Outgoing::Message(ChannelId::new(5), "**Standup**\nDaily sync\n<@10>".to_string())]);
+
+        data.write().await.remove::<DailyEventSignalKey>();
+        thread.join().unwrap();
+    }
+
+    #[tokio::test]
+    async fn predictListsAndChecksIds() {
+
+        let gateway: Arc<MemoryGateway> = Arc::new(MemoryGateway::default());
+        let data: Arc<RwLock<TypeMap>> = state();
+
+        assert_eq!(run(&gateway, &data, 10, "$predict -list").await, vec![said("predict.empty", &[])]);
+
+        data.write().await.get_mut::<User>().unwrap().insert(1, UserPrediction { user_id: 10, user_name: "user10".to_string(), prediction: "Rain tomorrow".to_string() });
+
+        let entry: String = tr(Lang::En, "predict.entry", &[("prediction", "Rain tomorrow"), ("user", "user10"), ("id", "1")]);
+        assert_eq!(run(&gateway, &data, 10, "$predict -list").await, vec![
+            Outgoing::Message(CHANNEL, format!("{}\n{}\n", tr(Lang::En, "predict.title", &[]), entry)),
+        ]);
+
+        // Removing needs Manage Messages and a numeric id
+        let out: Vec<Outgoing> = run(&gateway, &data, 10, "$predict -remove 1").await;
+        assert!(matches!(&out[..], [Outgoing::Message(_, M)] if M.contains("`$predict remove`")));
+
+        member(&gateway, 10, Permissions::MANAGE_MESSAGES);
+        let out: Vec<Outgoing> = run(&gateway, &data, 10, "$predict -remove one").await;
+        assert!(matches!(&out[..], [Outgoing::Message(_, M)] if M.contains("`text`: `one` is not a valid ID")));
+
+        let out: Vec<Outgoing> = run(&gateway, &data, 10, "$predict -shout").await;
+        assert!(matches!(&out[..], [Outgoing::Message(_, M)] if M.contains("`mode`: `shout` is not one of list, add, remove")));
+    }
+
+    #[tokio::test]
+    async fn santaSendsEveryoneSomeoneElse() {
+
+        let gateway: Arc<MemoryGateway> = Arc::new(MemoryGateway::default());
+        let data: Arc<RwLock<TypeMap>> = state();
+
+        for user in [10, 11, 12] {
+            assert!(run(&gateway, &data, user, "$santa register").await.is_empty());
+        }
+
+        // Starting is for the server managers
+        let out: Vec<Outgoing> = run(&gateway, &data, 10, "$santa start").await;
+        assert!(matches!(&out[..], [Outgoing::Message(_, M)] if M.contains("`$santa start`")));
+
+        member(&gateway, 10, Permissions::MANAGE_GUILD);
+        let out: Vec<Outgoing> = run(&gateway, &data, 10, "$santa start").await;
+
+        let mut gifters: Vec<u64> = vec![];
+        let mut giftees: Vec<u64> = vec![];
+
+        for o in out {
+            let (gifter, text): (UserId, String) = match o {
+                Outgoing::Dm(U, T) => (U, T),
+                other => panic!("Expected only DMs, got {:?}", other),
+            };
+
+            let giftee: u64 = [10, 11, 12].into_iter().find(|g| text == tr(Lang::En, "santa.assigned", &[("user", &g.to_string())])).unwrap();
+            assert_ne!(gifter.get(), giftee);
+
+            gifters.push(gifter.get());
+            giftees.push(giftee);
+        }
+
+        gifters.sort();
+        giftees.sort();
+        assert_eq!((gifters, giftees), (vec![10, 11, 12], vec![10, 11, 12]));
+    }
 }
@@ -0,0 +1,354 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serenity::prelude::{RwLock, TypeMap, TypeMapKey};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::args::ParsedArgs;
+use crate::command::{formatName, CommandSpec};
+use crate::error::RinrError;
+use crate::gateway::Invocation;
+use crate::helper::{CooldownRule, RinrOptions};
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Const Declaration
+
+// Used when the config has no rule for a command
+const DEFAULT_RULES: &[(&str, CooldownRule)] = &[
+    ("setvideo",    CooldownRule { user: 30, channel: 0, guild: 0 }),
+    ("fn",          CooldownRule { user: 0, channel: 0, guild: 60 }),
+];
+
+// Handlers that spawn chromedriver, $setvideo jobs take their slot in the background
+const EXPENSIVE: &[&str] = &["fn"];
+
+// Switches that only look at what is running, they are never on cooldown
+const CONTROLS: &[(&str, &str)] = &[("setvideo", "status"), ("setvideo", "cancel")];
+
+const QUEUE_TIMEOUT: u64 = 30;
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Enum Declaration
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Scope {
+    User,
+    Channel,
+    Guild,
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Struct Declaration
+
+// Command, scope and the id of the user, channel or guild
+type CooldownKey = (&'static str, Scope, u64);
+
+#[derive(Debug)]
+pub struct Cooldowns {
+    last_used: HashMap<CooldownKey, Instant>,
+    slots: Arc<Semaphore>,
+}
+
+impl TypeMapKey for Cooldowns {
+    type Value = Arc<Mutex<Cooldowns>>;
+}
+
+impl Cooldowns {
+    pub fn new(concurrency: usize) -> Cooldowns {
+        Cooldowns {
+            last_used: HashMap::new(),
+            slots: Arc::new(Semaphore::new(concurrency.max(1))),
+        }
+    }
+}
+
+// Held while the handler runs, the permit is given back when it is dropped
+pub struct Slot {
+    _permit: Option<OwnedSemaphorePermit>,
+    used: Option<UsedCooldown>,
+}
+
+// What a call wrote into the cooldowns, so it can be undone
+struct UsedCooldown {
+    state: Arc<Mutex<Cooldowns>>,
+    at: Instant,
+    previous: Vec<(CooldownKey, Option<Instant>)>,
+}
+
+impl Slot {
+
+    // Puts the cooldowns back how they were before the call, for handlers that turned it down
+    pub fn refund(mut self) {
+
+        let used: UsedCooldown = match self.used.take() {
+            Some(U) => U,
+            None => return,
+        };
+
+        let mut cooldowns = used.state.lock().unwrap();
+
+        for (key, previous) in used.previous {
+            // Another call went through in the meantime, its use stays
+            if cooldowns.last_used.get(&key) != Some(&used.at) {
+                continue;
+            }
+
+            match previous {
+                Some(P) => cooldowns.last_used.insert(key, P),
+                None => cooldowns.last_used.remove(&key),
+            };
+        }
+    }
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Checks the cooldowns of a command and takes a slot if it is expensive
+// Returns the seconds until it can be used again if not, the slot has to be kept until the handler is done
+pub async fn acquireSlot(cmd: &CommandSpec, args: &ParsedArgs, inv: &Invocation) -> Result<Slot, RinrError> {
+
+    if CONTROLS.iter().any(|(name, switch)| *name == cmd.name && args.bool(switch).unwrap_or(false)) {
+        return Ok(Slot { _permit: None, used: None });
+    }
+
+    let (state, rule): (Arc<Mutex<Cooldowns>>, CooldownRule) = {
+        let u_data = inv.data.read().await;
+
+        let state: Arc<Mutex<Cooldowns>> = match u_data.get::<Cooldowns>() {
+            Some(S) => S.clone(),
+            None => return Ok(Slot { _permit: None, used: None }),
+        };
+
+        let configured: Option<CooldownRule> = u_data.get::<RinrOptions>()
+                                                     .and_then(|c| c.lock().unwrap().cooldowns.get(cmd.name).cloned());
+
+        (state, configured.or(defaultRule(cmd.name)).unwrap_or_default())
+    };
+
+    let scopes: [(Scope, u64, u64); 3] = [
+        (Scope::User, inv.author.get(), rule.user),
+        (Scope::Channel, inv.channel_id.get(), rule.channel),
+        (Scope::Guild, inv.guild_id.map(|g| g.get()).unwrap_or(0), rule.guild),
+    ];
+
+    // Checks every scope before touching any of them, so a rejected call doesn't reset the others
+    let slots: Arc<Semaphore> = {
+        let cooldowns = state.lock().unwrap();
+        let now: Instant = Instant::now();
+
+        let remaining: u64 = scopes.iter()
+                                   .filter(|(_, _, secs)| *secs > 0)
+                                   .filter_map(|(scope, id, secs)| {
+                                       let last: &Instant = cooldowns.last_used.get(&(cmd.name, *scope, *id))?;
+                                       Duration::from_secs(*secs).checked_sub(now - *last)
+                                   })
+                                   .max()
+                                   .map_or(0, |d| d.as_secs() + 1);
+
+        if remaining > 0 {
+            return Err(RinrError::Cooldown { command: formatName(cmd), secs: remaining });
+        }
+
+        cooldowns.slots.clone()
+    };
+
+    let permit: Option<OwnedSemaphorePermit> = if EXPENSIVE.contains(&cmd.name) {
+        match tokio::time::timeout(Duration::from_secs(QUEUE_TIMEOUT), slots.acquire_owned()).await {
+            Ok(Ok(P)) => Some(P),
+            _ => return Err(RinrError::Cooldown { command: formatName(cmd), secs: QUEUE_TIMEOUT }),
+        }
+    } else {
+        None
+    };
+
+    // Recorded now so a second call can't slip in while the handler runs, refund takes it back
+    let now: Instant = Instant::now();
+
+    let previous: Vec<(CooldownKey, Option<Instant>)> = {
+        let mut cooldowns = state.lock().unwrap();
+
+        scopes.iter()
+              .filter(|(_, _, secs)| *secs > 0)
+              .map(|(scope, id, _)| ((cmd.name, *scope, *id), cooldowns.last_used.insert((cmd.name, *scope, *id), now)))
+              .collect()
+    };
+
+    Ok(Slot { _permit: permit, used: Some(UsedCooldown { state, at: now, previous }) })
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Waits for a slot without a timeout, for work that goes on after its handler is done
+pub async fn waitForSlot(data: &RwLock<TypeMap>) -> Option<OwnedSemaphorePermit> {
+
+    let slots: Arc<Semaphore> = data.read().await.get::<Cooldowns>()?.lock().unwrap().slots.clone();
+
+    slots.acquire_owned().await.ok()
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Gets the built in cooldown of a command
+fn defaultRule(name: &str) -> Option<CooldownRule> {
+    DEFAULT_RULES.iter().find(|(n, _)| *n == name).map(|(_, r)| r.clone())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serenity::all::{ChannelId, GuildId};
+    use crate::command::findCommand;
+    use crate::gateway::MemoryGateway;
+
+    // Shared data with a single rule for $say
+    fn state(rule: CooldownRule, concurrency: usize) -> Arc<RwLock<TypeMap>> {
+
+        let mut config: RinrOptions = RinrOptions::default();
+        config.cooldowns.insert("say".to_string(), rule);
+        config.cooldowns.insert("fn".to_string(), CooldownRule::default());
+
+        let mut data: TypeMap = TypeMap::new();
+        data.insert::<RinrOptions>(Arc::new(Mutex::new(config)));
+        data.insert::<Cooldowns>(Arc::new(Mutex::new(Cooldowns::new(concurrency))));
+        Arc::new(RwLock::new(data))
+    }
+
+    async fn tryUse(data: &Arc<RwLock<TypeMap>>, name: &str, author: u64, channel: u64, guild: u64) -> Result<Slot, RinrError> {
+
+        let gateway: Arc<MemoryGateway> = Arc::new(MemoryGateway::default());
+        let mut inv: Invocation = gateway.invocation(data.clone(), guild, author, "");
+        inv.channel_id = ChannelId::new(channel);
+        inv.guild_id = Some(GuildId::new(guild));
+
+        acquireSlot(findCommand(name).unwrap(), &ParsedArgs::default(), &inv).await
+    }
+
+    fn onCooldown(result: Result<Slot, RinrError>) -> bool {
+        matches!(result, Err(RinrError::Cooldown { .. }))
+    }
+
+    #[tokio::test]
+    async fn userScope() {
+
+        let data: Arc<RwLock<TypeMap>> = state(CooldownRule { user: 60, channel: 0, guild: 0 }, 1);
+
+        assert!(tryUse(&data, "say", 10, 1, 1).await.is_ok());
+        assert!(onCooldown(tryUse(&data, "say", 10, 2, 2).await));
+        assert!(tryUse(&data, "say", 11, 1, 1).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn channelScope() {
+
+        let data: Arc<RwLock<TypeMap>> = state(CooldownRule { user: 0, channel: 60, guild: 0 }, 1);
+
+        assert!(tryUse(&data, "say", 10, 1, 1).await.is_ok());
+        assert!(onCooldown(tryUse(&data, "say", 11, 1, 1).await));
+        assert!(tryUse(&data, "say", 10, 2, 1).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn guildScope() {
+
+        let data: Arc<RwLock<TypeMap>> = state(CooldownRule { user: 0, channel: 0, guild: 60 }, 1);
+
+        assert!(tryUse(&data, "say", 10, 1, 1).await.is_ok());
+        assert!(onCooldown(tryUse(&data, "say", 11, 2, 1).await));
+        assert!(tryUse(&data, "say", 10, 1, 2).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn otherCommandsAreNotAffected() {
+
+        let data: Arc<RwLock<TypeMap>> = state(CooldownRule { user: 60, channel: 60, guild: 60 }, 1);
+
+        assert!(tryUse(&data, "say", 10, 1, 1).await.is_ok());
+        assert!(tryUse(&data, "timer", 10, 1, 1).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn refundGivesTheCooldownBack() {
+
+        let data: Arc<RwLock<TypeMap>> = state(CooldownRule { user: 60, channel: 0, guild: 60 }, 1);
+
+        tryUse(&data, "say", 10, 1, 1).await.unwrap().refund();
+        assert!(tryUse(&data, "say", 10, 1, 1).await.is_ok());
+        assert!(onCooldown(tryUse(&data, "say", 10, 1, 1).await));
+    }
+
+    #[tokio::test]
+    async fn refundKeepsLaterUses() {
+
+        let data: Arc<RwLock<TypeMap>> = state(CooldownRule { user: 0, channel: 0, guild: 60 }, 1);
+
+        // Two calls got through before the cooldown was taken back, the second one still counts
+        let first: Slot = tryUse(&data, "say", 10, 1, 1).await.unwrap();
+        data.read().await.get::<Cooldowns>().unwrap().lock().unwrap().last_used.clear();
+        let _second: Slot = tryUse(&data, "say", 11, 1, 1).await.unwrap();
+
+        first.refund();
+        assert!(onCooldown(tryUse(&data, "say", 12, 1, 1).await));
+    }
+
+    #[tokio::test]
+    async fn controlsSkipTheCooldown() {
+
+        let data: Arc<RwLock<TypeMap>> = state(CooldownRule::default(), 1);
+        let gateway: Arc<MemoryGateway> = Arc::new(MemoryGateway::default());
+        let inv: Invocation = gateway.invocation(data.clone(), 1, 10, "");
+        let cmd: &CommandSpec = findCommand("setvideo").unwrap();
+
+        assert!(acquireSlot(cmd, &ParsedArgs::default(), &inv).await.is_ok());
+        assert!(onCooldown(acquireSlot(cmd, &ParsedArgs::default(), &inv).await));
+
+        let status: ParsedArgs = crate::args::parseArgs(cmd.args, "status").unwrap();
+        assert!(acquireSlot(cmd, &status, &inv).await.is_ok());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn expensiveCommandsShareTheCap() {
+
+        let data: Arc<RwLock<TypeMap>> = state(CooldownRule::default(), 2);
+
+        let first: Slot = tryUse(&data, "fn", 10, 1, 1).await.unwrap();
+        let _second: Slot = tryUse(&data, "fn", 11, 1, 2).await.unwrap();
+
+        // Both slots are taken, the third waits QUEUE_TIMEOUT and gives up
+        let started: tokio::time::Instant = tokio::time::Instant::now();
+
+        match tryUse(&data, "fn", 12, 1, 3).await {
+            Err(RinrError::Cooldown { secs, .. }) => assert_eq!(secs, QUEUE_TIMEOUT),
+            _ => panic!("Expected the queue to time out"),
+        }
+
+        assert!(started.elapsed() >= Duration::from_secs(QUEUE_TIMEOUT));
+
+        // Cheap commands don't need a slot
+        assert!(tryUse(&data, "say", 12, 1, 3).await.is_ok());
+
+        drop(first);
+        assert!(tryUse(&data, "fn", 12, 1, 3).await.is_ok());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn queuedCallGetsTheFreedSlot() {
+
+        let data: Arc<RwLock<TypeMap>> = state(CooldownRule::default(), 1);
+        let first: Slot = tryUse(&data, "fn", 10, 1, 1).await.unwrap();
+
+        let waiting = tokio::spawn({
+            let data: Arc<RwLock<TypeMap>> = data.clone();
+            async move { tryUse(&data, "fn", 11, 1, 2).await.is_ok() }
+        });
+
+        tokio::time::sleep(Duration::from_secs(QUEUE_TIMEOUT - 1)).await;
+        drop(first);
+
+        assert!(waiting.await.unwrap());
+    }
+}
@@ -0,0 +1,253 @@
+/*
+    Runs yt-dlp, ffmpeg and ffprobe for the intros
+
+    the tools are started directly with their arguments, nothing goes through a shell so links and paths stay as they are
+    every run has a timeout and can be cancelled, the tool gets killed in both cases
+    a tool that exits with an error is reported with its exit status and what it wrote to stderr
+*/
+use std::process::{Output, Stdio};
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde_json::Value;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader, Lines};
+use tokio::process::{Child, ChildStderr, ChildStdout, Command};
+use tokio::sync::watch;
+
+use crate::args::formatTimestamp;
+use crate::error::RinrError;
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Const Declaration
+
+// Checked at startup with the argument that prints their version
+const TOOLS: &[(&str, &str)] = &[("yt-dlp", "--version"), ("ffmpeg", "-version"), ("ffprobe", "-version")];
+
+pub const INFO_TIMEOUT: Duration = Duration::from_secs(30);
+pub const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(180);
+pub const RENDER_TIMEOUT: Duration = Duration::from_secs(60);
+pub const PROBE_TIMEOUT: Duration = Duration::from_secs(15);
+
+// Marks the progress lines of yt-dlp so they can't be mixed up with anything else it prints
+const PROGRESS: &str = "RINR_PROGRESS ";
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Struct Declaration
+
+// Cancels every tool that was started with it, clones share the signal
+#[derive(Debug, Clone)]
+pub struct CancelToken {
+    signal: Arc<watch::Sender<bool>>,
+}
+
+
+impl Default for CancelToken {
+    fn default() -> CancelToken {
+        CancelToken {
+            signal: Arc::new(watch::channel(false).0),
+        }
+    }
+}
+
+impl CancelToken {
+
+    pub fn cancel(&self) {
+        self.signal.send_replace(true);
+    }
+
+    // Resolves once cancel was called, right away if it was already
+    pub async fn cancelled(&self) {
+        let mut recv: watch::Receiver<bool> = self.signal.subscribe();
+        let _ = recv.wait_for(|c| *c).await;
+    }
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Runs a tool until it exits, times out or gets cancelled, only a successful exit is Ok
+// Every line the tool writes to stdout is handed to on_line as it comes
+pub async fn runTool(program: &str, args: Vec<String>, limit: Duration, cancel: &CancelToken, on_line: Option<&(dyn Fn(&str) + Sync)>) -> Result<Output, RinrError> {
+
+    println!("Running {} {:?}", program, args);
+
+    // Dropping the child kills the tool, that is how timeouts and cancelling stop it
+    let mut child: Child = match Command::new(program)
+                                         .args(&args)
+                                         .stdin(Stdio::null())
+                                         .stdout(Stdio::piped())
+                                         .stderr(Stdio::piped())
+                                         .kill_on_drop(true)
+                                         .spawn() {
+        Ok(C) => C,
+        Err(E) => return Err(RinrError::Download(format!("Unable to start {}: {}", program, E))),
+    };
+
+    let mut stdout: Lines<BufReader<ChildStdout>> = BufReader::new(child.stdout.take().unwrap()).lines();
+    let mut stderr: ChildStderr = child.stderr.take().unwrap();
+
+    let output = async {
+        let mut out: Vec<u8> = vec![];
+        let mut err: Vec<u8> = vec![];
+
+        // Both are read at the same time, a full pipe would block the tool otherwise
+        let read_out = async {
+            while let Ok(Some(line)) = stdout.next_line().await {
+                if let Some(F) = on_line {
+                    F(&line);
+                }
+                out.extend_from_slice(line.as_bytes());
+                out.push(b'\n');
+            }
+        };
+
+        let _ = tokio::join!(read_out, stderr.read_to_end(&mut err));
+
+        child.wait().await.map(|status| Output { status, stdout: out, stderr: err })
+    };
+
+    let res: Result<std::io::Result<Output>, tokio::time::error::Elapsed> = tokio::select! {
+        res = tokio::time::timeout(limit, output) => res,
+        _ = cancel.cancelled() => return Err(RinrError::Cancelled(program.to_string())),
+    };
+
+    match res {
+        Ok(Ok(O)) if O.status.success() => Ok(O),
+        Ok(Ok(O)) => Err(RinrError::Download(format!("{} exited with {}: {}", program, O.status, String::from_utf8_lossy(&O.stderr).trim()))),
+        Ok(Err(E)) => Err(RinrError::Download(format!("Unable to start {}: {}", program, E))),
+        Err(_) => Err(RinrError::Download(format!("{} timed out after {}s", program, limit.as_secs()))),
+    }
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Checks that the tools can be started, intros can't be set without them
+pub async fn checkTools() {
+
+    for (tool, version) in TOOLS {
+
+        match Command::new(tool).arg(version).stdin(Stdio::null()).output().await {
+            Ok(O) if O.status.success() => {
+                let stdout: String = String::from_utf8_lossy(&O.stdout).to_string();
+                println!("Found {}: {}", tool, stdout.lines().next().unwrap_or("").trim());
+            },
+            Ok(O) => println!("WARNING: {} exited with {}, $setvideo might not work", tool, O.status),
+            Err(E) => println!("WARNING: {} is missing ({}), $setvideo won't work until it is installed", tool, E),
+        }
+    }
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Gets the title and the length in ms of a video without downloading it
+pub async fn fetchInfo(link: &str, cancel: &CancelToken) -> Result<(Option<String>, Option<u64>), RinrError> {
+
+    let output: Output = runTool("yt-dlp", vec![
+        "--dump-single-json".to_string(),
+        "--no-playlist".to_string(),
+        "--".to_string(),
+        link.to_string(),
+    ], INFO_TIMEOUT, cancel, None).await?;
+
+    let info: Value = match serde_json::from_slice(&output.stdout) {
+        Ok(V) => V,
+        Err(E) => return Err(RinrError::Download(format!("yt-dlp gave no readable info: {}", E))),
+    };
+
+    // Links to a playlist without a video in them give the whole playlist
+    if info["_type"].as_str() == Some("playlist") {
+        return Err(RinrError::InvalidLink(link.to_string()));
+    }
+
+    let title: Option<String> = info["title"].as_str().map(|t| t.to_string());
+    let duration: Option<u64> = info["duration"].as_f64().map(|d| (d * 1000.0) as u64);
+
+    Ok((title, duration))
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Downloads the audio between start and end in ms as an mp3, on_percent gets the progress when yt-dlp reports it
+// It goes to a temporary file first, the file at out is only replaced once the download worked
+pub async fn downloadSection(link: &str, start: u64, end: u64, out: &str, cancel: &CancelToken, on_percent: &(dyn Fn(u8) + Sync)) -> Result<(), RinrError> {
+
+    let temp: String = out.replace(".mp3", ".download.mp3");
+
+    // Left over from a download that was cancelled
+    let _ = tokio::fs::remove_file(&temp).await;
+
+    // bestaudio/best falls back to the muxed file for sites like Twitch that have no separate audio
+    runTool("yt-dlp", vec![
+        "-o".to_string(), temp.clone(),
+        "--download-sections".to_string(), format!("*{}-{}", formatTimestamp(start), formatTimestamp(end)),
+        "-x".to_string(),
+        "--audio-format".to_string(), "mp3".to_string(),
+        "-f".to_string(), "bestaudio/best".to_string(),
+        "--no-playlist".to_string(),
+        "--force-overwrites".to_string(),
+        "--force-keyframes-at-cuts".to_string(),
+        "--newline".to_string(),
+        "--progress-template".to_string(), format!("download:{}%(progress._percent_str)s", PROGRESS),
+        "--".to_string(),
+        link.to_string(),
+    ], DOWNLOAD_TIMEOUT, cancel, Some(&|line: &str| {
+        let percent: Option<f64> = line.strip_prefix(PROGRESS).and_then(|p| p.trim().trim_end_matches('%').parse::<f64>().ok());
+
+        if let Some(P) = percent {
+            on_percent(P.clamp(0.0, 100.0) as u8);
+        }
+    })).await?;
+
+    // yt-dlp exits fine for some sites without writing anything
+    if tokio::fs::metadata(&temp).await.is_err() {
+        return Err(RinrError::Download(format!("yt-dlp didn't write {}", temp)));
+    }
+
+    tokio::fs::rename(&temp, out).await?;
+
+    Ok(())
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Cuts length ms starting at start out of a file and turns it into an mp3
+pub async fn cutAudio(source: &str, target: &str, start: u64, length: u64, cancel: &CancelToken) -> Result<(), RinrError> {
+
+    runTool("ffmpeg", vec![
+        "-y".to_string(),
+        "-ss".to_string(), formatTimestamp(start),
+        "-t".to_string(), formatTimestamp(length),
+        "-i".to_string(), source.to_string(),
+        "-vn".to_string(),
+        "-codec:a".to_string(), "libmp3lame".to_string(),
+        "-q:a".to_string(), "2".to_string(),
+        target.to_string(),
+    ], RENDER_TIMEOUT, cancel, None).await?;
+
+    Ok(())
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Gets the length of an audio file in ms
+pub async fn probeDuration(path: &str, cancel: &CancelToken) -> Result<u64, RinrError> {
+
+    let probe_res: Result<Output, RinrError> = runTool("ffprobe", vec![
+        "-v".to_string(), "error".to_string(),
+        "-show_entries".to_string(), "format=duration".to_string(),
+        "-of".to_string(), "default=noprint_wrappers=1:nokey=1".to_string(),
+        path.to_string(),
+    ], PROBE_TIMEOUT, cancel, None).await;
+
+    // ffprobe only exits with an error on files it can't read
+    let output: Output = match probe_res {
+        Err(RinrError::Download(E)) if E.starts_with("ffprobe exited") => return Err(RinrError::InvalidUpload(E)),
+        other => other?,
+    };
+
+    match String::from_utf8_lossy(&output.stdout).trim().parse::<f64>() {
+        Ok(D) => Ok((D * 1000.0) as u64),
+        Err(_) => Err(RinrError::InvalidUpload("ffprobe didn't report a duration".to_string())),
+    }
+}
@@ -0,0 +1,220 @@
+/*
+    Audio effects and rendering of intros
+
+    the effect options of $setvideo are turned into an ffmpeg filter chain that is stored with the intro
+    the downloaded clip stays untouched next to the intro as <file>.source.mp3, the intro itself is rendered from it
+    with the chain and the loudness target, so it can be rendered again later without downloading anything
+*/
+use crate::args::{ArgError, Expected, ParsedArgs};
+use crate::download::{runTool, CancelToken, RENDER_TIMEOUT};
+use crate::error::RinrError;
+use crate::gateway::Invocation;
+use crate::helper::{LoudnessOptions, RinrOptions};
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Const Declaration
+
+const SPEED: (f64, f64) = (0.5, 2.0);
+const PITCH: (f64, f64) = (-12.0, 12.0);       // Semitones
+const BASS: (f64, f64) = (-20.0, 20.0);        // dB
+
+// Cuts quiet parts at the start, run on the reversed clip it does the end as well
+const SILENCE: &str = "silenceremove=start_periods=1:start_threshold=-50dB";
+
+// Sample rate everything is rendered at, discord plays 48kHz
+const RATE: u32 = 48000;
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Builds the filter chain from the effect options of $setvideo, empty if none were given
+pub fn effectChain(args: &ParsedArgs) -> Result<String, ArgError> {
+
+    let mut chain: Vec<String> = vec![];
+
+    if args.bool("trimsilence").unwrap_or(false) {
+        chain.push(format!("{},areverse,{},areverse", SILENCE, SILENCE));
+    }
+
+    if args.bool("reverse").unwrap_or(false) {
+        chain.push("areverse".to_string());
+    }
+
+    let speed: f64 = checkRange(args, "speed", SPEED)?.unwrap_or(1.0);
+    let pitch: f64 = checkRange(args, "pitch", PITCH)?.unwrap_or(0.0);
+
+    // Pitch changes the rate and with it the speed, atempo takes that back out again
+    let mut tempo: f64 = speed;
+
+    if pitch != 0.0 {
+        let factor: f64 = 2f64.powf(pitch / 12.0);
+        chain.push(format!("aresample={},asetrate={:.0},aresample={}", RATE, RATE as f64 * factor, RATE));
+        tempo /= factor;
+    }
+
+    chain.extend(tempoFilters(tempo));
+
+    if let Some(gain) = checkRange(args, "bass", BASS)? {
+        chain.push(format!("bass=g={}", gain));
+    }
+
+    if let Some(fade) = args.timestamp("fadein") {
+        chain.push(format!("afade=t=in:d={:.3}", fade as f64 / 1000.0));
+    }
+
+    // Fading the reversed clip in is a fade out that doesn't need to know how long the clip ends up
+    if let Some(fade) = args.timestamp("fadeout") {
+        chain.push(format!("areverse,afade=t=in:d={:.3},areverse", fade as f64 / 1000.0));
+    }
+
+    Ok(chain.join(","))
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Gets a number argument and checks it against its range
+fn checkRange(args: &ParsedArgs, key: &str, (min, max): (f64, f64)) -> Result<Option<f64>, ArgError> {
+
+    match args.float(key) {
+        Some(V) if V < min || V > max => Err(ArgError::invalid(key, &V.to_string(), Expected::Between(min.to_string(), max.to_string()))),
+        other => Ok(other),
+    }
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Splits a tempo change into atempo filters, older ffmpeg versions only take 0.5 to 2 in one of them
+fn tempoFilters(mut tempo: f64) -> Vec<String> {
+
+    let mut filters: Vec<String> = vec![];
+
+    while tempo > 2.0 {
+        filters.push("atempo=2".to_string());
+        tempo /= 2.0;
+    }
+
+    while tempo < 0.5 {
+        filters.push("atempo=0.5".to_string());
+        tempo /= 0.5;
+    }
+
+    if (tempo - 1.0).abs() > 0.0001 {
+        filters.push(format!("atempo={:.4}", tempo));
+    }
+
+    filters
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Renders an intro from its source with the effects and brings it to the loudness target
+pub async fn renderIntro(source: String, target: String, chain: &str, loudness: &LoudnessOptions, cancel: &CancelToken) -> Result<(), RinrError> {
+
+    let filter: String = match chain.is_empty() {
+        true => loudnormFilter(loudness),
+        false => format!("{},{}", chain, loudnormFilter(loudness)),
+    };
+
+    runTool("ffmpeg", vec![
+        "-y".to_string(),
+        "-i".to_string(), source,
+        "-vn".to_string(),
+        "-af".to_string(), filter,
+        "-ar".to_string(), RATE.to_string(),
+        "-codec:a".to_string(), "libmp3lame".to_string(),
+        "-q:a".to_string(), "2".to_string(),
+        target,
+    ], RENDER_TIMEOUT, cancel, None).await?;
+
+    Ok(())
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Builds the ffmpeg filter for the loudness target, values outside of what loudnorm takes are clamped
+fn loudnormFilter(loudness: &LoudnessOptions) -> String {
+    format!("loudnorm=I={}:TP={}:LRA=11", loudness.target.clamp(-70.0, -5.0), loudness.true_peak.clamp(-9.0, 0.0))
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Gets the loudness target from the config
+pub async fn loudnessOptions(inv: &Invocation) -> LoudnessOptions {
+    match inv.data.read().await.get::<RinrOptions>() {
+        Some(C) => C.lock().unwrap().loudness.clone(),
+        None => LoudnessOptions::default(),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::parseArgs;
+    use crate::command::findCommand;
+
+    // Parses the options like $setvideo does and builds the chain from them
+    fn chain(input: &str) -> Result<String, ArgError> {
+        effectChain(&parseArgs(findCommand("setvideo").unwrap().args, input)?)
+    }
+
+    #[test]
+    fn optionsBuildTheFilterChain() {
+
+        let silence: String = format!("{},areverse,{},areverse", SILENCE, SILENCE);
+
+        assert_eq!(chain("").unwrap(), "");
+        assert_eq!(chain("reverse").unwrap(), "areverse");
+        assert_eq!(chain("trimsilence").unwrap(), silence);
+        assert_eq!(chain("speed=1.5").unwrap(), "atempo=1.5000");
+        assert_eq!(chain("speed=1").unwrap(), "");
+        assert_eq!(chain("bass=-20").unwrap(), "bass=g=-20");
+        assert_eq!(chain("fadein=0.5").unwrap(), "afade=t=in:d=0.500");
+        assert_eq!(chain("fadeout=1:00.25").unwrap_err().key, "fadeout");
+        assert_eq!(chain("fadeout=1.25").unwrap(), "areverse,afade=t=in:d=1.250,areverse");
+
+        // Pitch changes the rate, the tempo makes up for it so only the pitch is left
+        assert_eq!(chain("pitch=12").unwrap(), "aresample=48000,asetrate=96000,aresample=48000,atempo=0.5000");
+        assert_eq!(chain("pitch=-12 speed=0.5").unwrap(), "aresample=48000,asetrate=24000,aresample=48000");
+        assert_eq!(chain("pitch=-12 speed=2").unwrap(), "aresample=48000,asetrate=24000,aresample=48000,atempo=2,atempo=2.0000");
+
+        // Always in the same order, whatever order they were given in
+        assert_eq!(chain("fadein=1 speed=0.5 bass=3 reverse trimsilence").unwrap(),
+                   format!("{},areverse,atempo=0.5000,bass=g=3,afade=t=in:d=1.000", silence));
+    }
+
+    #[test]
+    fn valuesOutOfRangeAreRejected() {
+
+        for input in ["speed=0.5", "speed=2", "pitch=-12", "pitch=12", "bass=-20", "bass=20", "fadein=0", "fadein=10", "fadeout=10", "length=1", "length=10"] {
+            assert!(chain(input).is_ok(), "{}", input);
+        }
+
+        for (input, key) in [
+            ("speed=0.49", "speed"), ("speed=2.01", "speed"), ("speed=0", "speed"),
+            ("pitch=-12.5", "pitch"), ("pitch=13", "pitch"),
+            ("bass=-21", "bass"), ("bass=20.5", "bass"),
+            ("fadein=10.5", "fadein"), ("fadeout=11", "fadeout"),
+            ("length=0.5", "length"), ("length=11", "length"),
+        ] {
+            assert_eq!(chain(input).map_err(|e| e.key), Err(key.to_string()), "{}", input);
+        }
+    }
+
+    #[test]
+    fn tempoIsSplitIntoSteps() {
+
+        assert_eq!(tempoFilters(1.0), Vec::<String>::new());
+        assert_eq!(tempoFilters(5.0), vec!["atempo=2", "atempo=2", "atempo=1.2500"]);
+        assert_eq!(tempoFilters(0.2), vec!["atempo=0.5", "atempo=0.5", "atempo=0.8000"]);
+    }
+
+    #[test]
+    fn loudnessIsClamped() {
+
+        let loudness = |target: f64, true_peak: f64| loudnormFilter(&LoudnessOptions { target, true_peak });
+
+        assert_eq!(loudness(-16.0, -1.5), "loudnorm=I=-16:TP=-1.5:LRA=11");
+        assert_eq!(loudness(-90.0, 3.0), "loudnorm=I=-70:TP=0:LRA=11");
+    }
+}
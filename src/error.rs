@@ -0,0 +1,111 @@
+/*
+    Error type shared by the command handlers
+
+    handlers return it instead of replying on their own, the dispatcher logs the full cause
+    and renders a reply in the language of the guild
+*/
+use std::fmt;
+
+use crate::args::ArgError;
+use crate::command::{MAX_UPLOAD_MB, MAX_UPLOAD_MINUTES};
+use crate::intro::MAX_INTROS;
+use crate::lang::{tr, Lang};
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Enum Declaration
+
+#[derive(Debug)]
+pub enum RinrError {
+    InvalidLink(String),                // The link that didn't match
+    InvalidUpload(String),              // Attachment with the wrong type, size or length
+    Download(String),                   // yt-dlp, ffmpeg or the fortnite shop failed, holds the cause
+    Cancelled(String),                  // The tool that was running
+    Busy(String),                       // Name of the job the author still has running
+    TooManyIntros,
+    Voice(String),
+    Parse(ArgError),
+    Permission {
+        command: String,                // Command or command and mode, like "event delete"
+        permissions: Vec<String>,
+        roles: Vec<u64>,
+    },
+    Cooldown {
+        command: String,
+        secs: u64,
+    },
+    Storage(String),                    // Config or prediction file couldn't be written
+    Discord(Box<serenity::Error>),      // Boxed, it is bigger than everything else together
+}
+
+
+impl RinrError {
+
+    // What the user gets to see, the cause only goes into the log
+    pub fn render(&self, lang: Lang) -> String {
+        match self {
+            RinrError::InvalidLink(_) => tr(lang, "error.invalid_link", &[]),
+            RinrError::InvalidUpload(_) => tr(lang, "error.invalid_upload", &[("mb", &MAX_UPLOAD_MB.to_string()), ("minutes", &MAX_UPLOAD_MINUTES.to_string())]),
+            RinrError::Download(_) => tr(lang, "error.download", &[]),
+            RinrError::Cancelled(_) => tr(lang, "error.cancelled", &[]),
+            RinrError::Busy(N) => tr(lang, "job.running", &[("name", N)]),
+            RinrError::TooManyIntros => tr(lang, "intro.too_many", &[("max", &MAX_INTROS.to_string())]),
+            RinrError::Voice(_) => tr(lang, "error.voice", &[]),
+            RinrError::Parse(E) => E.render(lang),
+            RinrError::Permission { command, permissions, roles } => {
+                let mut reason: String = tr(lang, "general.denied", &[("command", command)]);
+
+                if !permissions.is_empty() {
+                    reason.push_str(&tr(lang, "general.denied_permission", &[("permissions", &permissions.join(", "))]));
+                }
+
+                if !roles.is_empty() {
+                    let roles: String = roles.iter().map(|r| format!("<@&{}>", r)).collect::<Vec<String>>().join(", ");
+                    reason.push_str(&tr(lang, "general.denied_roles", &[("roles", &roles)]));
+                }
+
+                reason
+            },
+            RinrError::Cooldown { command, secs } => tr(lang, "general.cooldown", &[("command", command), ("secs", &secs.to_string())]),
+            RinrError::Storage(_) => tr(lang, "error.storage", &[]),
+            RinrError::Discord(_) => tr(lang, "error.discord", &[]),
+        }
+    }
+}
+
+impl fmt::Display for RinrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RinrError::InvalidLink(L) => write!(f, "Invalid link: {}", L),
+            RinrError::InvalidUpload(E) => write!(f, "Invalid upload: {}", E),
+            RinrError::Download(E) => write!(f, "Download failed: {}", E),
+            RinrError::Cancelled(T) => write!(f, "{} was cancelled", T),
+            RinrError::Busy(N) => write!(f, "{} is still running", N),
+            RinrError::TooManyIntros => write!(f, "Intro limit of {} reached", MAX_INTROS),
+            RinrError::Voice(E) => write!(f, "Voice failed: {}", E),
+            RinrError::Parse(E) => write!(f, "Invalid argument {}", E),
+            RinrError::Permission { command, .. } => write!(f, "Missing permission for {}", command),
+            RinrError::Cooldown { command, secs } => write!(f, "{} on cooldown for {}s", command, secs),
+            RinrError::Storage(E) => write!(f, "Storage failed: {}", E),
+            RinrError::Discord(E) => write!(f, "Discord request failed: {:?}", E),
+        }
+    }
+}
+
+impl From<ArgError> for RinrError {
+    fn from(err: ArgError) -> RinrError {
+        RinrError::Parse(err)
+    }
+}
+
+impl From<serenity::Error> for RinrError {
+    fn from(err: serenity::Error) -> RinrError {
+        RinrError::Discord(Box::new(err))
+    }
+}
+
+impl From<std::io::Error> for RinrError {
+    fn from(err: std::io::Error) -> RinrError {
+        RinrError::Storage(err.to_string())
+    }
+}
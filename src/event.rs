@@ -8,27 +8,28 @@
     or if it receives a new event it will rebuild the config struct and save it to the disk, if it timeouts then the event is run and the next one is scheduled
 
 */
-use std::{env, str::FromStr, sync::mpsc::{Receiver, RecvTimeoutError, Sender}, time::Duration};
+use std::{str::FromStr, sync::{mpsc::{Receiver, RecvTimeoutError, Sender}, Arc, Mutex}};
 
-use chrono::{Datelike, Local, NaiveDate, NaiveTime};
-use regex::Regex;
-use lazy_static::lazy_static;
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime};
 
 use serde::{Deserialize, Serialize};
-use serenity::http::Http;
-use serenity::all::{ChannelId, Context, Message, UserId};
+use serenity::all::{ChannelId, UserId};
 use tokio::runtime::Runtime;
 
 
+use crate::args::{ArgError, ArgReason, Expected, ParsedArgs};
+use crate::clock::{self, Clock};
+use crate::error::RinrError;
+use crate::gateway::{Gateway, Invocation};
 use crate::helper::{
-    writeConfig,
+    saveConfig,
     DailyEvent, DailyEventSignalKey,
     Diff, EventSignal,
     RinrOptions, States, Timeslice
 };
+use crate::lang::{tr, Lang};
 
 
-const EVENT:        &str = "$event ";
 const CREATE:       &str = "create";
 const LIST:         &str = "list";
 const SUBSCRIBE:    &str = "subscribe";
@@ -37,23 +38,18 @@ const DELETE:       &str = "delete";
 const CHANNEL:      &str = "channel";
 
 
-lazy_static! {
-
-    // Mode Selection
-    static ref reg_mode: Regex = Regex::new(r"(create|list|delete|subscribe|unsubscribe|channel)").unwrap();
-
-    // Mode Add
-    static ref reg_name: Regex = Regex::new(r"name=\[(.*?)\]").unwrap();
-    static ref reg_desc: Regex = Regex::new(r"description=\[(.*?)\]").unwrap();
-    static ref reg_time: Regex = Regex::new(r"time=\[([01]\d|2[0-3]):([0-5]\d)\]").unwrap();
-    static ref reg_sub: Regex = Regex::new(r"subscribe=\[(0|1)\]").unwrap();
-    static ref reg_command: Regex = Regex::new(r"command=\[(.*?)\]").unwrap();
-    static ref reg_date: Regex = Regex::new(r"date=(?P<day>\d{2})/(?P<month>\d{2})/(?P<year>\d{4})").unwrap();
-    static ref reg_interval: Regex = Regex::new(r"(?i)interval=(Daily|Weekly|Monthly|Yearly|Once)").unwrap();
-
-    // Mode Remove, Subscribe, Unsubscribe
-    static ref reg_id: Regex = Regex::new(r"id=(\d+)").unwrap();
+// Everything the event thread does after the config is unlocked again, so a slow send never holds up the handlers
+#[derive(Default)]
+struct Outbox {
+    messages: Vec<(ChannelId, String)>,
+    save: Option<PendingSave>,
+}
 
+// The config is saved once the lock is given up, the confirmation only goes out once it is written
+struct PendingSave {
+    channel: ChannelId,
+    lang: Lang,
+    confirm: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -67,6 +63,17 @@ pub enum Command {
     Invalid,
 }
 
+impl Outbox {
+
+    fn send(&mut self, channel: ChannelId, out: String) {
+        self.messages.push((channel, out));
+    }
+
+    fn save(&mut self, channel: ChannelId, lang: Lang, confirm: Option<String>) {
+        self.save = Some(PendingSave { channel, lang, confirm });
+    }
+}
+
 impl Command {
     fn from_str(command: &str) -> Option<Self> {
         match command {
@@ -84,63 +91,61 @@ impl Command {
 
 //--------------------------------------------------------------------------------------------------------------------------
 // Event thread that listens on a channel and handles Events and EventSignals
-pub fn loops(mut config: RinrOptions, recv: Receiver<EventSignal>) {
-    
-    let http: Http = Http::new(&env::var("TOKEN").unwrap());
+pub fn loops(shared: Arc<Mutex<RinrOptions>>, recv: Receiver<EventSignal>, gateway: Arc<dyn Gateway>, clock: Arc<dyn Clock>) {
     
     loop {
 
-        (&mut config).resortEvents();
+        // Lock is only held while working on the config, never while waiting
+        let duration: u64 = {
+            let mut config = shared.lock().unwrap();
+
+            let curr_time: NaiveDateTime = clock.now();
 
-        // let mut duration: Option<i64> = None;
-        let curr_time: NaiveTime = chrono::offset::Local::now().time();
+            (&mut *config).resortEvents(curr_time.time());
 
-        let duration = if let Some(first) = config.events.first() {
-            let diff = curr_time.timeDif(&first.timestamp);
+            if let Some(first) = config.events.first() {
+                let diff = curr_time.time().timeDif(&first.timestamp);
 
-            if diff <= 0 {
-                0
+                if diff <= 0 {
+                    0
+                } else {
+                    diff as u64
+                }
             } else {
-                diff as u64
+                3600
             }
-        } else {
-            3600
         };
 
-        // if let Some(first) = config.events.first() {
-        //     duration = Some(curr_time.timeDif(&first.timestamp));
-        //     if duration.unwrap() == 0 {
-        //         println!("Continuing");
-        //         continue;
-        //     }
-        // }
-
-
-        // if duration == None {
-        //     duration = Some(3600);
-        // }
-
         println!("Time until next Event/Timeout: {}s", duration);
 
-        match recv.recv_timeout(Duration::from_secs(duration)) {
-            Ok(EventSig) => match EventSig.event_type {
-                Command::List => listEvent(&config, EventSig.channel_id, &http), 
-                Command::Delete => deleteEvent(&mut config, &http, &EventSig),
-                Command::Create => createEvent(&mut config, &http, &EventSig),
-                Command::Subscribe => subscribeEvent(&mut config, &EventSig, &http),
-                Command::Unsubscribe => unsubscribeEvent(&mut config, &EventSig, &http),
-                Command::Channel => channelEvent(&mut config, EventSig.channel_id, &http),
-                Command::Invalid => continue,
-            },
-            Err(RecvTimeoutError::Timeout) => {
-                println!("Timeout!");
-                activateEvent(&mut config, &http);
-                continue; 
-            },
-            Err(e) => {
-                println!("Error while recv: {:?}", e);
+        let received: Result<EventSignal, RecvTimeoutError> = clock.wait(&recv, duration);
+
+        // Same here, sending and writing only start once the handlers can get to the config again
+        let outbox: Outbox = {
+            let mut config = shared.lock().unwrap();
+
+            match received {
+                Ok(EventSig) => match EventSig.event_type {
+                    Command::List => listEvent(&config, &EventSig), 
+                    Command::Delete => deleteEvent(&mut config, &EventSig),
+                    Command::Create => createEvent(&mut config, &EventSig),
+                    Command::Subscribe => subscribeEvent(&mut config, &EventSig),
+                    Command::Unsubscribe => unsubscribeEvent(&mut config, &EventSig),
+                    Command::Channel => channelEvent(&mut config, &EventSig),
+                    Command::Invalid => continue,
+                },
+                Err(RecvTimeoutError::Timeout) => {
+                    println!("Timeout!");
+                    activateEvent(&mut config, clock.now().date())
+                },
+                Err(RecvTimeoutError::Disconnected) => {
+                    println!("Event channel closed, stopping event thread");
+                    return;
+                }
             }
         };
+
+        deliver(outbox, &shared, gateway.as_ref());
     }
 
 }
@@ -148,16 +153,17 @@ pub fn loops(mut config: RinrOptions, recv: Receiver<EventSignal>) {
 
 //--------------------------------------------------------------------------------------------------------------------------
 // Executes an event 
-fn activateEvent(mut config: &mut RinrOptions, http: &Http) {
+fn activateEvent(mut config: &mut RinrOptions, current_day: NaiveDate) -> Outbox {
 
-    if config.bot_channel == None {
-        return;
-    }
+    let mut outbox: Outbox = Outbox::default();
+
+    let bot_channel: ChannelId = match config.bot_channel {
+        Some(C) => ChannelId::new(C),
+        None => return outbox,
+    };
         
     if let Some(current_event) = config.events.first() {
 
-        let current_day: NaiveDate = Local::now().date_naive();
-
         let mut remove: bool = false;
 
         println!("Activated event {}!", current_event.name);
@@ -165,27 +171,27 @@ fn activateEvent(mut config: &mut RinrOptions, http: &Http) {
         match current_event.interval {
             Timeslice::Daily => (),
             Timeslice::Weekly => {
-                if !((current_day - current_event.date).num_days().abs() % 7 == 0) {
+                if (current_day - current_event.date).num_days().abs() % 7 != 0 {
                     println!("Not the right weekday, skipping!");
-                    return;
+                    return outbox;
                 }
             },
             Timeslice::Monthly => {
                 if current_day.day() != current_event.date.day() {
                     println!("Not the right day of the month, skipping!");
-                    return;
+                    return outbox;
                 }
             },
             Timeslice::Yearly => {
                 if (current_day.year() != current_event.date.year()) || (current_day.month() != current_event.date.month()) || (current_day.day() != current_event.date.day()) {
                     println!("Not the right day of the year, skipping!");
-                    return;
+                    return outbox;
                 }
             },
             Timeslice::Once => {
                 if (current_day != current_event.date) && (current_day < current_event.date) {
                     println!("Not the right day, skipping!");
-                    return;
+                    return outbox;
                 }
 
                 println!("This event will be removed!");
@@ -197,7 +203,7 @@ fn activateEvent(mut config: &mut RinrOptions, http: &Http) {
 
         let mut subsc_string: Vec<String> = current_event.subscribers.iter().map(|id| format!("<@{}>", id.get())).collect();
         
-        if subsc_string.len() == 0 {
+        if subsc_string.is_empty() {
             subsc_string.push("---".to_string());
         }
 
@@ -208,19 +214,20 @@ fn activateEvent(mut config: &mut RinrOptions, http: &Http) {
             subsc_string.join("\n"),       
         );
 
-        let rt: Runtime = Runtime::new().unwrap();
+        outbox.send(bot_channel, event_string);
 
-        rt.block_on(sendWrapper(ChannelId::new(config.bot_channel.unwrap()), http, event_string));
-
-        if current_event.command != None {
-            rt.block_on(sendWrapper(ChannelId::new(config.bot_channel.unwrap()), http, current_event.command.clone().unwrap()));
+        if let Some(C) = &current_event.command {
+            outbox.send(bot_channel, C.clone());
         }
 
         if remove {
-            config.removeEntry(current_event.id);
-            rt.block_on(writeConfig(Some(&config)));
+            let id: u64 = current_event.id;
+            config.removeEntry(id);
+            outbox.save(bot_channel, config.languageFor(config.bot_guild), None);
         }
     }
+
+    outbox
 }
 
 
@@ -228,147 +235,187 @@ fn activateEvent(mut config: &mut RinrOptions, http: &Http) {
 
 //--------------------------------------------------------------------------------------------------------------------------
 // Subscribe to an event
-fn subscribeEvent(mut config: &mut RinrOptions, data: &EventSignal, http: &Http) {
+fn subscribeEvent(mut config: &mut RinrOptions, data: &EventSignal) -> Outbox {
 
-    let ret: bool = config.subscribe(data.event_info.clone().unwrap());
+    let mut outbox: Outbox = Outbox::default();
 
-    
-    if ret {
-        let rt: Runtime = Runtime::new().unwrap();
-        rt.block_on(writeConfig(Some(&config)));
-        rt.block_on(sendWrapper(data.channel_id, http, "Successfully subscribed to Event".to_string()));
+    if config.subscribe(data.event_info.clone().unwrap()) {
+        let lang: Lang = signalLang(config, data);
+        outbox.save(data.channel_id, lang, Some(tr(lang, "event.subscribed", &[])));
     }
+
+    outbox
 }
 
 //--------------------------------------------------------------------------------------------------------------------------
 // Unsubscribe from an event
-fn unsubscribeEvent(mut config: &mut RinrOptions, data: &EventSignal, http: &Http) {
+fn unsubscribeEvent(mut config: &mut RinrOptions, data: &EventSignal) -> Outbox {
 
-    let ret: bool = config.unsubscribe(data.event_info.clone().unwrap());
+    let mut outbox: Outbox = Outbox::default();
 
-    if ret {
-        let rt: Runtime = Runtime::new().unwrap();
-        rt.block_on(writeConfig(Some(&config)));
-        rt.block_on(sendWrapper(data.channel_id, http, "Successfully unsubscribed from Event".to_string()));
+    if config.unsubscribe(data.event_info.clone().unwrap()) {
+        let lang: Lang = signalLang(config, data);
+        outbox.save(data.channel_id, lang, Some(tr(lang, "event.unsubscribed", &[])));
     }
 
+    outbox
 }
 
 //--------------------------------------------------------------------------------------------------------------------------
 // Deletes an event
-fn deleteEvent(mut config: &mut RinrOptions, http: &Http, data: &EventSignal) { 
+fn deleteEvent(mut config: &mut RinrOptions, data: &EventSignal) -> Outbox { 
 
-    config.removeEntry(data.event_info.clone().unwrap().id);
+    let mut outbox: Outbox = Outbox::default();
 
-    let rt: Runtime = Runtime::new().unwrap();
-
-    rt.block_on(writeConfig(Some(&config)));
+    config.removeEntry(data.event_info.clone().unwrap().id);
 
-    rt.block_on(sendWrapper(data.channel_id, http, "Successfully deleted event".to_string()));
+    let lang: Lang = signalLang(config, data);
+    outbox.save(data.channel_id, lang, Some(tr(lang, "event.deleted", &[])));
 
+    outbox
 }
 
 //--------------------------------------------------------------------------------------------------------------------------
 // Creates the events
-fn createEvent(mut config: &mut RinrOptions, http: &Http, data: &EventSignal) {
+fn createEvent(mut config: &mut RinrOptions, data: &EventSignal) -> Outbox {
 
-    if config.bot_channel == None {
-        let rt: Runtime = Runtime::new().unwrap();
-        rt.block_on(sendWrapper(data.channel_id, http, "Please configure the Bot channel!\nYou can do this by using $event channel.".to_string()));
-        return;
-    }
+    let mut outbox: Outbox = Outbox::default();
+    let lang: Lang = signalLang(config, data);
 
-    let rt: Runtime = Runtime::new().unwrap();
+    if config.bot_channel.is_none() {
+        outbox.send(data.channel_id, tr(lang, "event.no_channel", &[]));
+        return outbox;
+    }
 
     let event_to_add: DailyEvent = data.event_info.clone().unwrap();
 
     config.insert(event_to_add);
 
-    rt.block_on(writeConfig(Some(&config)));
+    outbox.save(data.channel_id, lang, Some(tr(lang, "event.created", &[])));
 
-    rt.block_on(sendWrapper(data.channel_id, http, "Successfully added event".to_string()));
+    outbox
 }
 
 
 //--------------------------------------------------------------------------------------------------------------------------
 // Sets the bot channel
-fn channelEvent(mut config: &mut RinrOptions, id: ChannelId, http: &Http) {
+fn channelEvent(mut config: &mut RinrOptions, data: &EventSignal) -> Outbox {
 
-    config.setChannel(id.get());
+    let mut outbox: Outbox = Outbox::default();
 
-    let rt: Runtime = Runtime::new().unwrap();
+    config.setChannel(data.channel_id.get(), data.guild_id.map(|g| g.get()));
 
-    rt.block_on(writeConfig(Some(&config)));
+    let lang: Lang = signalLang(config, data);
+    outbox.save(data.channel_id, lang, Some(tr(lang, "event.channel_set", &[])));
 
-    rt.block_on(sendWrapper(id, http, "Bot Channel Configured!".to_string()));
+    outbox
 }
 
 //--------------------------------------------------------------------------------------------------------------------------
 // Lists all available events in the bot channel
-fn listEvent(config: &RinrOptions, id: ChannelId, http: &Http) {
+fn listEvent(config: &RinrOptions, data: &EventSignal) -> Outbox {
 
-    let rt: Runtime = Runtime::new().unwrap();
+    let mut outbox: Outbox = Outbox::default();
 
-    
-    if config.bot_channel == None {
-        rt.block_on(sendWrapper(id, http, "Please configure the Bot channel!\nYou can do this by using $event channel.".to_string()));
-        return;
-    } 
+    let bot_channel: ChannelId = match config.bot_channel {
+        Some(C) => ChannelId::new(C),
+        None => {
+            outbox.send(data.channel_id, tr(signalLang(config, data), "event.no_channel", &[]));
+            return outbox;
+        },
+    };
         
-    let mut form: String = formatEvents(&config.events);
+    // The list is posted in the bot channel, so it uses the language of that guild
+    let lang: Lang = config.languageFor(config.bot_guild);
 
-    if form.len() == 0 {
-        form = String::from("No Events Available");
+    let mut form: String = formatEvents(&config.events, lang);
+
+    if form.is_empty() {
+        form = tr(lang, "event.none", &[]);
     } else {
         form.insert_str(0, "@silent ");
     }
 
+    outbox.send(bot_channel, form);
 
-    rt.block_on(sendWrapper(
-        ChannelId::new(
-            config.bot_channel.unwrap()
-        ), http,
-        form
-    ));
-
+    outbox
 }
 
 
 
 //--------------------------------------------------------------------------------------------------------------------------
 // Helps format subscribers
-fn formatEvents(events: &[DailyEvent]) -> String {
-    events.iter().map(formatEvent).collect::<Vec<String>>().join("\n\n")
+fn formatEvents(events: &[DailyEvent], lang: Lang) -> String {
+    events.iter().map(|e| formatEvent(e, lang)).collect::<Vec<String>>().join("\n\n")
 }
 
 //--------------------------------------------------------------------------------------------------------------------------
 // Formats event info to send into the bot channel
-fn formatEvent(event: &DailyEvent) -> String {
+fn formatEvent(event: &DailyEvent, lang: Lang) -> String {
     let mut subsc_string: Vec<String> = event.subscribers.iter().map(|id| format!("<@{}>", id.get())).collect();
 
-    if subsc_string.len() == 0 {
-        subsc_string.push("No Subscribers".to_string());
+    if subsc_string.is_empty() {
+        subsc_string.push(tr(lang, "event.no_subscribers", &[]));
+    }
+
+    tr(lang, "event.format", &[
+        ("name", &event.name),
+        ("id", &event.id.to_string()),
+        ("message", &event.message.clone().unwrap_or(tr(lang, "event.no_message", &[]))),
+        ("time", &event.timestamp.to_string()),
+        ("subscribers", &subsc_string.join(", ")),
+        ("command", &event.command.clone().unwrap_or(tr(lang, "event.no_command", &[]))),
+        ("date", &event.date.to_string()),
+        ("interval", &event.interval.to_string()),
+    ])
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Gets the language of the guild an EventSignal came from
+fn signalLang(config: &RinrOptions, data: &EventSignal) -> Lang {
+    config.languageFor(data.guild_id.map(|g| g.get()))
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Sends the messages and saves the config for an Outbox, a confirmation only goes out if the config could be written
+fn deliver(outbox: Outbox, shared: &Mutex<RinrOptions>, gateway: &dyn Gateway) {
+
+    if outbox.messages.is_empty() && outbox.save.is_none() {
+        return;
+    }
+
+    let rt: Runtime = Runtime::new().unwrap();
+
+    for (channel, out) in outbox.messages {
+        rt.block_on(sendWrapper(channel, gateway, out));
     }
 
-    format!(
-        "Event: {}\nID: {}\nMessage: {}\nTime: {}\nSubscribers: {}\nCommand: {}\nDate: {}\nInterval: {}\n\n",
-        event.name,
-        event.id,
-        event.message.as_deref().unwrap_or("No Message"),
-        event.timestamp,
-        subsc_string.join(", "),
-        event.command.as_deref().unwrap_or("No Command"),
-        event.date,
-        event.interval,
-    )
+    let save: PendingSave = match outbox.save {
+        Some(S) => S,
+        None => return,
+    };
+
+    match rt.block_on(saveConfig(shared)) {
+        Ok(()) => {
+            if let Some(C) = save.confirm {
+                rt.block_on(sendWrapper(save.channel, gateway, C));
+            }
+        },
+        Err(E) => {
+            println!("Unable to write config: {}", E);
+            rt.block_on(sendWrapper(save.channel, gateway, E.render(save.lang)));
+        },
+    }
 }
 
 
 //--------------------------------------------------------------------------------------------------------------------------
 // A wrapper to send messages to a channel, given an id
-async fn sendWrapper(id: ChannelId, http: &Http, out: String) {
-    if let Err(why) = id.say(http, out).await {
-        println!("Send Message failed. Error: {:?}", why);
+async fn sendWrapper(id: ChannelId, gateway: &dyn Gateway, out: String) {
+    if let Err(why) = gateway.send(id, out).await {
+        println!("Send Message failed. Error: {}", why);
     }
 }
 
@@ -376,306 +423,188 @@ async fn sendWrapper(id: ChannelId, http: &Http, out: String) {
 
 //--------------------------------------------------------------------------------------------------------------------------
 // Matches the command and sends an EventSignal to the Event Thread
-pub async fn eventHandler(msg: &Message, ctx: &Context) {
+pub async fn eventHandler(inv: &Invocation, args: &ParsedArgs) -> Result<(), RinrError> {
 
-    let mut u_data = ctx.data.write().await;
+    let selected_mode: Command = match args.text("mode").and_then(Command::from_str) {
+        Some(C) => C,
+        None => return Ok(()),
+    };
+
+    let event: Result<EventSignal, ArgError> = match selected_mode {
+        Command::Create => createInsert(inv, args).await,
+        Command::List => Ok(createListEvent(inv).await),
+        Command::Delete => createDeleteEvent(inv, args).await,
+        Command::Subscribe => createSubscribeEvent(inv, args).await,
+        Command::Unsubscribe => createUnsubscribeEvent(inv, args).await,
+        Command::Channel => Ok(createChannelEvent(inv).await),
+        Command::Invalid => Ok(createInvalid().await),
+    };
+
+    let event: EventSignal = event?;
+
+    let mut u_data = inv.data.write().await;
 
     let send: &mut Sender<EventSignal> = match u_data.get_mut::<DailyEventSignalKey>() {
         Some(D) => D,
-        None => return,
+        None => return Ok(()),
     };
 
-    let stripped_command: String = msg.content.clone()
-                                        .strip_prefix(EVENT)
-                                        .expect("Command got corrupted inside the program!")
-                                        .to_string();
-
-
-    if let Some(some_mode) = reg_mode.captures(&stripped_command) {
-        if let Some(selected_mode) = Command::from_str(some_mode.get(1).unwrap().as_str()) {
-            let event: EventSignal = match selected_mode {
-                Command::Create => createInsert(&msg).await,
-                Command::List => createListEvent(&msg).await,
-                Command::Delete => createDeleteEvent(&msg).await,
-                Command::Subscribe => createSubscribeEvent(&msg).await,
-                Command::Unsubscribe => createUnsubscribeEvent(&msg).await,
-                Command::Channel => createChannelEvent(&msg).await,
-                Command::Invalid => createInvalid().await,
-            };
-
-            let _ = send.send(event);
-        }
+    if let Err(E) = send.send(event) {
+        return Err(RinrError::Storage(format!("Event thread is gone: {}", E)));
     }
+
+    Ok(())
 }
 
 
 //--------------------------------------------------------------------------------------------------------------------------
 // Creates the EventSignal for subscribing to an event
-async fn createSubscribeEvent(msg: &Message) -> EventSignal {
-
-    if let Some(event_id) = reg_id.captures(&msg.content).and_then(|caps| caps.get(1).map(|m| m.as_str().parse::<u64>())) {
-        if let Ok(num) = event_id {
-            return EventSignal {
-                event_type: Command::Subscribe,
-                event_info: Some(DailyEvent { 
-                    name: "Subscribe".to_string(),
-                    id: num,
-                    message: None,
-                    timestamp: NaiveTime::default(),
-                    subscribers: vec![UserId::new(msg.author.id.get())],
-                    command: None,
-                    date: NaiveDate::default(),
-                    interval: Timeslice::default(),
-                }),
-                channel_id: msg.channel_id,
-            }
-        }
-    }
-
-    createInvalid().await
+async fn createSubscribeEvent(inv: &Invocation, args: &ParsedArgs) -> Result<EventSignal, ArgError> {
+
+    let num: u64 = parseId(args, "subscribe")?;
+
+    Ok(EventSignal {
+        event_type: Command::Subscribe,
+        event_info: Some(DailyEvent { 
+            name: "Subscribe".to_string(),
+            id: num,
+            message: None,
+            timestamp: NaiveTime::default(),
+            subscribers: vec![UserId::new(inv.author.get())],
+            command: None,
+            date: NaiveDate::default(),
+            interval: Timeslice::default(),
+        }),
+        channel_id: inv.channel_id,
+        guild_id: inv.guild_id,
+    })
 }
 
 
 //--------------------------------------------------------------------------------------------------------------------------
 // Creates the EventSignal for unsubscribing from an event
-async fn createUnsubscribeEvent(msg: &Message) -> EventSignal {
+async fn createUnsubscribeEvent(inv: &Invocation, args: &ParsedArgs) -> Result<EventSignal, ArgError> {
     
-    if let Some(event_id) = reg_id.captures(&msg.content).and_then(|caps| caps.get(1).map(|m| m.as_str().parse::<u64>())) {
-        if let Ok(num) = event_id {
-            return EventSignal {
-                event_type: Command::Unsubscribe,
-                event_info: Some(DailyEvent { 
-                    name: "Unsubscribe".to_string(),
-                    id: num,
-                    message: None,
-                    timestamp: NaiveTime::default(),
-                    subscribers: vec![UserId::new(msg.author.id.get())],
-                    command: None,
-                    date: NaiveDate::default(),
-                    interval: Timeslice::default(),
-                }),
-                channel_id: msg.channel_id,
-            }
-        }
-    }
-
-    createInvalid().await
+    let num: u64 = parseId(args, "unsubscribe")?;
+
+    Ok(EventSignal {
+        event_type: Command::Unsubscribe,
+        event_info: Some(DailyEvent { 
+            name: "Unsubscribe".to_string(),
+            id: num,
+            message: None,
+            timestamp: NaiveTime::default(),
+            subscribers: vec![UserId::new(inv.author.get())],
+            command: None,
+            date: NaiveDate::default(),
+            interval: Timeslice::default(),
+        }),
+        channel_id: inv.channel_id,
+        guild_id: inv.guild_id,
+    })
 }
 
 
 //--------------------------------------------------------------------------------------------------------------------------
 // Creates the EventSignal for event deletion
-async fn createDeleteEvent(msg: &Message) -> EventSignal {
+async fn createDeleteEvent(inv: &Invocation, args: &ParsedArgs) -> Result<EventSignal, ArgError> {
    
-    if let Some(event_id) = reg_id.captures(&msg.content).and_then(|caps| caps.get(1).map(|m| m.as_str().parse::<u64>())) {
-        if let Ok(num) = event_id {
-            return EventSignal { 
-                event_type: Command::Delete,
-                event_info: Some(DailyEvent {
-                    name: "Delete".to_string(),
-                    id: num,
-                    message: None,
-                    timestamp: NaiveTime::default(),
-                    subscribers: vec![],
-                    command: None,
-                    date: NaiveDate::default(),
-                    interval: Timeslice::default(),
-                }),
-                channel_id: msg.channel_id,
-            }
-        }
-    }
-
+    let num: u64 = parseId(args, "delete")?;
 
-    createInvalid().await
+    Ok(EventSignal { 
+        event_type: Command::Delete,
+        event_info: Some(DailyEvent {
+            name: "Delete".to_string(),
+            id: num,
+            message: None,
+            timestamp: NaiveTime::default(),
+            subscribers: vec![],
+            command: None,
+            date: NaiveDate::default(),
+            interval: Timeslice::default(),
+        }),
+        channel_id: inv.channel_id,
+        guild_id: inv.guild_id,
+    })
 }
 
 //--------------------------------------------------------------------------------------------------------------------------
 // Creates the EventSignal to create events
-async fn createInsert(msg: &Message) -> EventSignal {
+async fn createInsert(inv: &Invocation, args: &ParsedArgs) -> Result<EventSignal, ArgError> {
 
-    let text: &String = &msg.content;
-
-    let mut event_name: Option<String> = None;
-    let mut event_description: Option<String> = None;
-    let mut event_time: Option<NaiveTime> = None;
-    let mut event_subscribe: u64 = 1;
-    let mut event_command: Option<String> = None;
-    let mut event_date: Option<NaiveDate> = None;
-    let mut event_interval: Option<Timeslice> = None;
-
-    if let Some(name) = parse_first_match(text, &reg_name) {
-        println!("Name: {}", name);
-        event_name = Some(name);
-    }
-
-    if let Some(description) = parse_first_match(text, &reg_desc) {
-        println!("Description: {}", description);
-        event_description = Some(description);
-    }
-
-    if let Some(time) = parse_time(text) {
-        event_time = match time {
-            Some(T) => {
-                println!("Time: {}", T);
-                Some(T)
-            },
-            None => None,
-        }
-    }
-
-    if let Some(subscribe) = parse_subscribe(text) {
-        println!("Subscribe: {}", subscribe);
-        event_subscribe = match Some(subscribe).unwrap() {
-            true => msg.author.id.get(),
-            false => 1,
-        }
-    }
-
-    if let Some(command) = parse_first_match(text, &reg_command) {
-        println!("Command: {}", command);
-        event_command = Some(command);
-    }
-
-    if let Some(date) = parse_date(text) {
-        println!("Date: {}", date);
-        event_date = Some(date);
-        if event_date.unwrap() < Local::now().date_naive() {
-            event_date = Some(Local::now().date_naive());
-        }
-    }
-
-    if let Some(interval) = parse_interval(text) {
-        println!("Interval: {}", interval);
-        event_interval = Some(interval);
-    }
+    args.require("name", "create")?;
+    args.require("time", "create")?;
 
+    let event_name: String = args.text("name").unwrap_or_default().to_string();
+    let event_description: Option<String> = args.text("description").map(str::to_string);
+    let event_time: NaiveTime = args.time("time").unwrap_or_default();
+    let event_command: Option<String> = args.text("command").map(str::to_string);
 
+    let event_subscribe: u64 = match args.bool("subscribe").unwrap_or(true) {
+        true => inv.author.get(),
+        false => 1,
+    };
 
-    if event_name.is_none() || event_time.is_none() {
-        println!("Invalid Event");
-        return createInvalid().await;
-    }
-
-    if event_date == None {
-        event_date = Some(Local::now().date_naive());
-    }
-
-    if event_interval == None {
-        event_interval = Some(Timeslice::default());
-    }
-
+    // Dates in the past are moved to today
+    let today: NaiveDate = clock::today(&inv.data).await;
+    let event_date: NaiveDate = args.date("date")
+                                    .unwrap_or(today)
+                                    .max(today);
 
+    let event_interval: Timeslice = args.text("interval")
+                                        .and_then(|i| Timeslice::from_str(i).ok())
+                                        .unwrap_or_default();
 
     let user: UserId = UserId::new(event_subscribe);
     let v: Vec<UserId> = vec![user];
 
-    return EventSignal {
+    Ok(EventSignal {
         event_type: Command::Create, 
         event_info: Some(DailyEvent {
-            name: event_name.unwrap(),
+            name: event_name,
             id: 0,
             message: event_description,
             subscribers: v,
-            timestamp: event_time.unwrap(),
+            timestamp: event_time,
             command: event_command,
-            date: event_date.unwrap(),
-            interval: event_interval.unwrap(),
+            date: event_date,
+            interval: event_interval,
         }),
-        channel_id: msg.channel_id,
-    };
+        channel_id: inv.channel_id,
+        guild_id: inv.guild_id,
+    })
 }
 
 
 //--------------------------------------------------------------------------------------------------------------------------
 // Creates an invalid event signal as a fallback
 async fn createInvalid() -> EventSignal {
-    EventSignal {event_type: Command::Invalid, event_info: None, channel_id: ChannelId::new(1)}
+    EventSignal {event_type: Command::Invalid, event_info: None, channel_id: ChannelId::new(1), guild_id: None}
 } 
 
 
 //--------------------------------------------------------------------------------------------------------------------------
-// Regex parsing function for the date
-fn parse_date(text: &str) -> Option<NaiveDate> {
-    
-    let mut d: u32 = 0;
-    let mut m: u32 = 0;
-    let mut y: i32 = 0;
-    
-    if let Some(dates) = reg_date.captures(text) {
-        
-        if let Some(day) = dates.name("day") {
-            d = day.as_str().parse::<u32>().unwrap();
-        }
+// Gets the event id needed for delete, subscribe and unsubscribe
+fn parseId(args: &ParsedArgs, mode: &str) -> Result<u64, ArgError> {
 
-        if let Some(month) = dates.name("month") {
-            m = month.as_str().parse::<u32>().unwrap();
-        }
+    args.require("id", mode)?;
 
-        if let Some(year) = dates.name("year") {
-            y = year.as_str().parse::<i32>().unwrap();
-        }
-
-        return NaiveDate::from_ymd_opt(y, m, d);
+    match args.int("id") {
+        Some(I) if I >= 0 => Ok(I as u64),
+        Some(I) => Err(ArgError::invalid("id", &I.to_string(), Expected::Id)),
+        None => Err(ArgError::new("id", ArgReason::Required)),
     }
-
-    None
-}
-
-//--------------------------------------------------------------------------------------------------------------------------
-// Regex parsing function for the date
-fn parse_interval(text: &str) -> Option<Timeslice> {
-    reg_interval.captures(text).and_then(|caps| {
-        let time = caps.get(1).map(|t| t.as_str());
-        match time {
-            Some(t) => {
-                match Timeslice::from_str(t) {
-                    Ok(f) => Some(f),
-                    Err(_) => None,
-                } 
-            }
-            None => None,
-        }
-    })
-}
-
-
-
-
-//--------------------------------------------------------------------------------------------------------------------------
-// Regex parsing function for the subscribers
-fn parse_subscribe(text: &str) -> Option<bool> {
-    reg_sub.captures(text).and_then(|caps| caps.get(1).map(|m| m.as_str() == "1"))
-}
-
-//--------------------------------------------------------------------------------------------------------------------------
-// Regex parsing function for the time
-fn parse_time(text: &str) -> Option<Option<NaiveTime>> {
-    reg_time.captures(text).and_then(|caps| {
-        let hours = caps.get(1).map(|m| m.as_str().parse::<u32>().ok());
-        let minutes = caps.get(2).map(|m| m.as_str().parse::<u32>().ok());
-        match (hours, minutes) {
-            (Some(Some(h)), Some(Some(m))) => Some(NaiveTime::from_hms_opt(h, m, 0)),
-            _ => None,
-        }
-    })
-}
-
-//--------------------------------------------------------------------------------------------------------------------------
-// Regex parsing function for the first match of strings
-fn parse_first_match(text: &str, regex: &Regex) -> Option<String> {
-    regex.captures(text).and_then(|caps| caps.get(1).map(|m| m.as_str().to_string()))
 }
 
 
 //--------------------------------------------------------------------------------------------------------------------------
 // Creates the EventSignal to set the bot channel
-async fn createChannelEvent(msg: &Message) -> EventSignal {
-    EventSignal {event_type: Command::Channel, event_info: None, channel_id: msg.channel_id}
+async fn createChannelEvent(inv: &Invocation) -> EventSignal {
+    EventSignal {event_type: Command::Channel, event_info: None, channel_id: inv.channel_id, guild_id: inv.guild_id}
 }
 
 //--------------------------------------------------------------------------------------------------------------------------
 // Creates the EventSignal to List all Events
-async fn createListEvent(msg: &Message) -> EventSignal {
-    EventSignal {event_type: Command::List, event_info: None, channel_id: msg.channel_id}
+async fn createListEvent(inv: &Invocation) -> EventSignal {
+    EventSignal {event_type: Command::List, event_info: None, channel_id: inv.channel_id, guild_id: inv.guild_id}
 }
@@ -1,14 +1,16 @@
 use std::process::{Child, Command};
 
-use crate::helper::say;
+use crate::error::RinrError;
+use crate::gateway::Invocation;
+use crate::lang::{tr, Lang};
 
-use serenity::{client::Context, model::prelude::Message};
 
 
 use thirtyfour::{prelude::*, ChromeCapabilities};
 use scraper::{Html, Selector};
 
 
+#[allow(dead_code)] // The whole shop, only the new items are posted for now
 const FULL: &str = "https://fortnite.gg/shop?game=br";
 const NEW_ONLY: &str = "https://fortnite.gg/shop?game=br&different";
 const IMG_PREPEND: &str = "https://fortnite.gg";
@@ -24,20 +26,34 @@ struct FnShop<'a> {
 }
 
 
-pub async fn fortniteWrapper(msg: &Message, ctx: &Context) {
+pub async fn fortniteWrapper(inv: &Invocation) -> Result<(), RinrError> {
     
-    match runFortnite(&msg, &ctx).await {
-        Ok(_) => println!("Successfully accessed Fortnite Shop info"),
-        Err(e) =>  println!("Failure: {}", e),
+    match runFortnite(inv).await {
+        Ok(true) => println!("Successfully accessed Fortnite Shop info"),
+        Ok(false) => return Err(RinrError::Download("No items found in the Fortnite Shop".to_string())),
+        Err(e) => return Err(RinrError::Download(format!("Fortnite Shop: {}", e))),
     }
+
+    Ok(())
 }
 
-async fn runFortnite(msg: &Message, ctx: &Context) -> Result<(), thirtyfour::error::WebDriverError> {
-
-    let mut caps: ChromeCapabilities = DesiredCapabilities::chrome();
+// Returns false if the shop had no items, chromedriver is stopped whether that worked or not
+async fn runFortnite(inv: &Invocation) -> Result<bool, thirtyfour::error::WebDriverError> {
 
     let mut chromedriver: Child = startDriver();
 
+    let res: Result<bool, thirtyfour::error::WebDriverError> = scrapeShop(inv).await;
+
+    chromedriver.kill().expect("Unable to kill Chromedriver");
+    chromedriver.wait().expect("Failed to wait on chromedriver process");
+
+    res
+}
+
+async fn scrapeShop(inv: &Invocation) -> Result<bool, thirtyfour::error::WebDriverError> {
+
+    let mut caps: ChromeCapabilities = DesiredCapabilities::chrome();
+
     // TODO: url mode full new
     let _mode: String = String::default();
 
@@ -52,26 +68,24 @@ async fn runFortnite(msg: &Message, ctx: &Context) -> Result<(), thirtyfour::err
 
     let page_source: String = driver.source().await?;
 
-    let form: Option<String> = processDocument(&page_source);
+    let lang: Lang = inv.lang().await;
+
+    let form: Option<String> = processDocument(&page_source, lang);
+
+    let found: bool = form.is_some();
 
-    match form {
-        Some(F) => {
-            for part in splitMessage(F, DELIMITER).iter() {
-                say(msg, ctx, part.to_string()).await
-            }         
-        },
-        None => println!("Err"),
+    if let Some(F) = form {
+        for part in splitMessage(F, DELIMITER).iter() {
+            inv.say(part.to_string()).await
+        }         
     }
 
     driver.quit().await?;
 
-    chromedriver.kill().expect("Unable to kill Chromedriver");
-    chromedriver.wait().expect("Failed to wait on chromedriver process");
-
-    Ok(())
+    Ok(found)
 }
 
-fn processDocument(page_source: &String) -> Option<String> {
+fn processDocument(page_source: &str, lang: Lang) -> Option<String> {
 
     let document: Html = Html::parse_document(page_source);
 
@@ -81,8 +95,7 @@ fn processDocument(page_source: &String) -> Option<String> {
     let price_selector: Selector = Selector::parse("div.fn-item-price").unwrap();
     let time_selector: Selector = Selector::parse("span.js-bundle-countdown").unwrap();
 
-    let mut item_list: Vec<FnShop> = vec![];
-    item_list.reserve(50);
+    let mut item_list: Vec<FnShop> = Vec::with_capacity(50);
 
     for item_div in document.select(&item_selector) {
         // Get image source
@@ -97,52 +110,54 @@ fn processDocument(page_source: &String) -> Option<String> {
             .select(&name_selector)
             .next()
             .map(|n| n.text().collect::<Vec<_>>().join(""))
-            .unwrap_or_else(|| "No Name".to_string());
+            .unwrap_or_else(|| tr(lang, "fortnite.no_name", &[]));
 
         // Get item price
         let price = item_div
             .select(&price_selector)
             .next()
             .map(|p| p.text().collect::<Vec<_>>().join(""))
-            .unwrap_or_else(|| "No Price".to_string());
+            .unwrap_or_else(|| tr(lang, "fortnite.no_price", &[]));
 
         let time = item_div
             .select(&time_selector)
             .next()
             .map(|t| t.text().collect::<Vec<_>>().join(""))
-            .unwrap_or_else(|| "Unknown Time Remaining".to_string());
+            .unwrap_or_else(|| tr(lang, "fortnite.no_time", &[]));
 
         
         item_list.push(FnShop { 
             url: img_src,
-            name: name,
-            time: time,
-            price: price 
+            name,
+            time,
+            price 
         });
     }
 
-    formatItems(item_list)
+    formatItems(item_list, lang)
 }
 
 
 
-fn formatItems(items: Vec<FnShop>) -> Option<String> {
+fn formatItems(items: Vec<FnShop>, lang: Lang) -> Option<String> {
     let mut form = String::new();
 
     for item in items {
         let formatted_item = format!(
-            "{}\n\n**Name:** {}\n**Price:** {}\n**Time Remaining:** {}\n{}\n",
-            format!("{}/{}", IMG_PREPEND, item.url),
-            item.name,
-            item.price,
-            item.time,
+            "{}{}\n",
+            tr(lang, "fortnite.item", &[
+                ("image", &format!("{}/{}", IMG_PREPEND, item.url)),
+                ("name", &item.name),
+                ("price", &item.price),
+                ("time", &item.time),
+            ]),
             DELIMITER
         );
 
         form.push_str(&formatted_item);
     }
 
-    if form.len() != 0 {
+    if !form.is_empty() {
         Some(form)
     } else {
         None
@@ -0,0 +1,465 @@
+/*
+    Everything the handlers need from discord goes through the Gateway trait
+
+    SerenityGateway talks to discord, MemoryGateway keeps everything in memory so commands can be run without a connection
+    a command is handed to its handler as an Invocation, which carries the gateway, the shared data and who sent it where
+*/
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use serenity::all::{ChannelId, ChannelType, CommandInteraction, CreateAttachment, CreateMessage, EditMessage, GuildId, GuildRef, Member, MessageId, Permissions, RoleId, UserId};
+use serenity::async_trait;
+use serenity::model::prelude::Message;
+use serenity::prelude::{Context, RwLock, TypeMap};
+
+use crate::error::RinrError;
+use crate::helper::guildLang;
+use crate::lang::{tr, Lang};
+use crate::voice::{leaveVoice, playFile, stopPlaying};
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Const Declaration
+
+// First id of the messages a MemoryGateway sends, far above the ones the simulator gives the user
+const BOT_MESSAGES: u64 = 1 << 32;
+
+// Discord refuses longer messages, counted in characters
+pub const MAX_MESSAGE: usize = 2000;
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Trait Declaration
+
+#[async_trait]
+pub trait Gateway: Send + Sync {
+
+    async fn send(&self, channel: ChannelId, content: String) -> Result<MessageId, RinrError>;
+
+    async fn edit(&self, channel: ChannelId, message: MessageId, content: String) -> Result<(), RinrError>;
+
+    // Uploads a file from disk under another name
+    async fn sendFile(&self, channel: ChannelId, path: String, filename: String, content: String) -> Result<(), RinrError>;
+
+    async fn delete(&self, channel: ChannelId, message: MessageId) -> Result<(), RinrError>;
+
+    async fn dm(&self, user: UserId, content: String) -> Result<(), RinrError>;
+
+    async fn member(&self, guild: GuildId, user: UserId) -> Result<Option<MemberInfo>, RinrError>;
+
+    // Display names of everyone in a guild, for lists that would otherwise look up members one by one
+    async fn memberNames(&self, guild: GuildId) -> Result<HashMap<UserId, String>, RinrError>;
+
+    async fn voiceChannel(&self, guild: GuildId, user: UserId) -> Result<Option<ChannelId>, RinrError>;
+
+    // How many people are in a voice channel, bots don't count
+    async fn voiceCount(&self, guild: GuildId, channel: ChannelId) -> Result<usize, RinrError>;
+
+    // Joins or moves to the channel and plays the file until it is done, volume 1.0 is the file as is
+    // The bot stays in the call afterwards, the queue leaves once nothing is waiting anymore
+    async fn play(&self, guild: GuildId, channel: ChannelId, path: String, volume: f32) -> Result<(), RinrError>;
+
+    // Both return false if the bot isn't in voice in that guild
+    async fn stop(&self, guild: GuildId) -> Result<bool, RinrError>;
+
+    async fn leave(&self, guild: GuildId) -> Result<bool, RinrError>;
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Struct Declaration
+
+#[derive(Debug, Clone, Default)]
+pub struct MemberInfo {
+    pub name: String,               // Display name on the server
+    pub roles: Vec<RoleId>,
+    pub permissions: Permissions,
+}
+
+// A single command as the handlers see it
+#[derive(Clone)]
+pub struct Invocation {
+    pub gateway: Arc<dyn Gateway>,
+    pub data: Arc<RwLock<TypeMap>>,
+    pub author: UserId,
+    pub author_name: String,
+    pub channel_id: ChannelId,
+    pub guild_id: Option<GuildId>,
+    pub message_id: Option<MessageId>,  // None for slash commands and offline runs
+    pub content: String,
+    pub attachments: Vec<AttachmentInfo>,   // Always empty for slash commands
+}
+
+#[derive(Debug, Clone)]
+pub struct AttachmentInfo {
+    pub url: String,
+    pub filename: String,
+    pub size: u64,                      // As reported by discord, 0 if unknown
+}
+
+#[derive(Clone)]
+pub struct SerenityGateway {
+    ctx: Context,
+}
+
+// Everything a MemoryGateway was asked to do, in order
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outgoing {
+    Message(ChannelId, String),
+    Edit(ChannelId, MessageId, String),
+    File(ChannelId, String, String),    // Path and message
+    Delete(ChannelId, MessageId),
+    Dm(UserId, String),
+    Play(GuildId, ChannelId, String, f32),
+    Stop(GuildId),
+    Leave(GuildId),
+}
+
+#[derive(Debug, Default)]
+pub struct MemoryGateway {
+    pub outbox: Mutex<Vec<Outgoing>>,
+    pub members: Mutex<HashMap<(GuildId, UserId), MemberInfo>>,
+    pub voice: Mutex<HashMap<(GuildId, UserId), ChannelId>>,
+    pub bot_voice: Mutex<HashSet<GuildId>>,
+    pub sent: AtomicU64,                // Messages sent so far, they get ids after the ones of the simulated user
+    pub echo: bool,                     // Prints everything as it is sent, used by the simulator
+}
+
+impl fmt::Display for Outgoing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Outgoing::Message(C, M) => write!(f, "[#{}] {}", C, M),
+            Outgoing::Edit(C, I, M) => write!(f, "[#{}] edited {}: {}", C, I, M),
+            Outgoing::File(C, P, M) => write!(f, "[#{}] {} (file {})", C, M, P),
+            Outgoing::Delete(C, M) => write!(f, "[#{}] deleted message {}", C, M),
+            Outgoing::Dm(U, M) => write!(f, "[DM @{}] {}", U, M),
+            Outgoing::Play(G, C, P, V) => write!(f, "[voice {}/{}] playing {} at {}%", G, C, P, (V * 100.0).round()),
+            Outgoing::Stop(G) => write!(f, "[voice {}] stopped", G),
+            Outgoing::Leave(G) => write!(f, "[voice {}] left", G),
+        }
+    }
+}
+
+
+impl Invocation {
+
+    pub fn fromMessage(msg: &Message, ctx: &Context) -> Invocation {
+        Invocation {
+            gateway: Arc::new(SerenityGateway::new(ctx.clone())),
+            data: ctx.data.clone(),
+            author: msg.author.id,
+            author_name: msg.author.name.clone(),
+            channel_id: msg.channel_id,
+            guild_id: msg.guild_id,
+            message_id: Some(msg.id),
+            content: msg.content.clone(),
+            attachments: msg.attachments.iter().map(|a| AttachmentInfo {
+                url: a.url.clone(),
+                filename: a.filename.clone(),
+                size: a.size as u64,
+            }).collect(),
+        }
+    }
+
+    // Slash commands have no message of their own, the content is rebuilt from the options
+    pub fn fromInteraction(interaction: &CommandInteraction, ctx: &Context, content: String) -> Invocation {
+        Invocation {
+            gateway: Arc::new(SerenityGateway::new(ctx.clone())),
+            data: ctx.data.clone(),
+            author: interaction.user.id,
+            author_name: interaction.user.name.clone(),
+            channel_id: interaction.channel_id,
+            guild_id: interaction.guild_id,
+            message_id: None,
+            content,
+            attachments: vec![],
+        }
+    }
+
+    pub async fn lang(&self) -> Lang {
+        guildLang(&self.data, self.guild_id).await
+    }
+
+    // Writes to the channel the command came from
+    pub async fn say(&self, out: String) {
+        if let Err(why) = self.gateway.send(self.channel_id, out).await {
+            println!("Send Message failed. Error: {}", why)
+        }
+    }
+
+    // Writes lines to the channel, split over as many messages as it takes to stay under the limit
+    pub async fn sayLines(&self, lines: Vec<String>) {
+        for out in packLines(lines) {
+            self.say(out).await;
+        }
+    }
+
+    // Writes a catalog message in the language of the guild
+    pub async fn reply(&self, key: &str, vars: &[(&str, &str)]) {
+        let lang: Lang = self.lang().await;
+        self.say(tr(lang, key, vars)).await;
+    }
+
+    // Deletes the message of the command, if there is one
+    pub async fn delete(&self) {
+
+        let message: MessageId = match self.message_id {
+            Some(M) => M,
+            None => return,
+        };
+
+        if let Err(why) = self.gateway.delete(self.channel_id, message).await {
+            println!("Delete Message failed Error: {}", why)
+        }
+    }
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Joins lines into as few messages under MAX_MESSAGE as possible, a line that is too long on its own is cut
+pub fn packLines(lines: Vec<String>) -> Vec<String> {
+
+    let mut messages: Vec<String> = vec![];
+    let mut current: String = String::new();
+    let mut length: usize = 0;
+
+    for line in lines {
+
+        let line: String = line.chars().take(MAX_MESSAGE).collect();
+        let line_length: usize = line.chars().count();
+
+        if length > 0 && length + 1 + line_length > MAX_MESSAGE {
+            messages.push(std::mem::take(&mut current));
+            length = 0;
+        }
+
+        if length > 0 {
+            current.push('\n');
+            length += 1;
+        }
+
+        current.push_str(&line);
+        length += line_length;
+    }
+
+    if length > 0 {
+        messages.push(current);
+    }
+
+    messages
+}
+
+
+impl SerenityGateway {
+    pub fn new(ctx: Context) -> SerenityGateway {
+        SerenityGateway { ctx }
+    }
+}
+
+#[async_trait]
+impl Gateway for SerenityGateway {
+
+    async fn send(&self, channel: ChannelId, content: String) -> Result<MessageId, RinrError> {
+        Ok(channel.say(&self.ctx.http, content).await?.id)
+    }
+
+    async fn edit(&self, channel: ChannelId, message: MessageId, content: String) -> Result<(), RinrError> {
+        channel.edit_message(&self.ctx.http, message, EditMessage::new().content(content)).await?;
+        Ok(())
+    }
+
+    async fn sendFile(&self, channel: ChannelId, path: String, filename: String, content: String) -> Result<(), RinrError> {
+
+        let mut file: CreateAttachment = CreateAttachment::path(&path).await?;
+        file.filename = filename;
+
+        channel.send_files(&self.ctx.http, vec![file], CreateMessage::new().content(content)).await?;
+        Ok(())
+    }
+
+    async fn delete(&self, channel: ChannelId, message: MessageId) -> Result<(), RinrError> {
+        channel.delete_message(&self.ctx.http, message).await?;
+        Ok(())
+    }
+
+    async fn dm(&self, user: UserId, content: String) -> Result<(), RinrError> {
+        let channel = user.to_user(&self.ctx.http).await?.create_dm_channel(&self.ctx.http).await?;
+        channel.say(&self.ctx.http, content).await?;
+        Ok(())
+    }
+
+    async fn member(&self, guild: GuildId, user: UserId) -> Result<Option<MemberInfo>, RinrError> {
+
+        let member: Member = guild.member(&self.ctx, user).await?;
+
+        Ok(Some(MemberInfo {
+            name: member.display_name().to_string(),
+            permissions: member.permissions(&self.ctx.cache).unwrap_or_default(),
+            roles: member.roles,
+        }))
+    }
+
+    async fn memberNames(&self, guild: GuildId) -> Result<HashMap<UserId, String>, RinrError> {
+
+        // The cache only has everyone once the member list of the guild was loaded
+        let cached: Option<HashMap<UserId, String>> = self.ctx.cache.guild(guild)
+            .filter(|g| g.members.len() as u64 >= g.member_count)
+            .map(|g| g.members.iter().map(|(id, m)| (*id, m.display_name().to_string())).collect());
+
+        if let Some(C) = cached {
+            return Ok(C);
+        }
+
+        let mut names: HashMap<UserId, String> = HashMap::new();
+        let mut after: Option<UserId> = None;
+
+        // Discord hands out at most 1000 members per request
+        loop {
+            let page: Vec<Member> = guild.members(&self.ctx.http, Some(1000), after).await?;
+
+            names.extend(page.iter().map(|m| (m.user.id, m.display_name().to_string())));
+            after = page.last().map(|m| m.user.id);
+
+            if page.len() < 1000 {
+                return Ok(names);
+            }
+        }
+    }
+
+    async fn voiceChannel(&self, guild: GuildId, user: UserId) -> Result<Option<ChannelId>, RinrError> {
+
+        // Guilds that aren't cached have nobody in voice
+        let cached: GuildRef = match self.ctx.cache.guild(guild) {
+            Some(G) => G,
+            None => return Ok(None),
+        };
+
+        let channel: Option<ChannelId> = cached.voice_states.get(&user).and_then(|v| v.channel_id);
+
+        Ok(channel.filter(|c| cached.channels.get(c).is_some_and(|c| matches!(c.kind, ChannelType::Voice | ChannelType::Stage))))
+    }
+
+    async fn voiceCount(&self, guild: GuildId, channel: ChannelId) -> Result<usize, RinrError> {
+
+        let cached: GuildRef = match self.ctx.cache.guild(guild) {
+            Some(G) => G,
+            None => return Ok(0),
+        };
+
+        // Voice states carry the member when the member list isn't cached
+        let count: usize = cached.voice_states.values()
+            .filter(|v| v.channel_id == Some(channel))
+            .filter(|v| !cached.members.get(&v.user_id).or(v.member.as_ref()).is_some_and(|m| m.user.bot))
+            .count();
+
+        Ok(count)
+    }
+
+    async fn play(&self, guild: GuildId, channel: ChannelId, path: String, volume: f32) -> Result<(), RinrError> {
+        playFile(&self.ctx, guild, channel, path, volume).await
+    }
+
+    async fn stop(&self, guild: GuildId) -> Result<bool, RinrError> {
+        stopPlaying(&self.ctx, guild).await
+    }
+
+    async fn leave(&self, guild: GuildId) -> Result<bool, RinrError> {
+        leaveVoice(&self.ctx, guild).await
+    }
+}
+
+
+impl MemoryGateway {
+
+    // A command from a user in a guild of this gateway, for the tests
+    #[cfg(test)]
+    pub fn invocation(self: &Arc<Self>, data: Arc<RwLock<TypeMap>>, guild: u64, author: u64, content: &str) -> Invocation {
+        Invocation {
+            gateway: self.clone(),
+            data,
+            author: UserId::new(author),
+            author_name: format!("user{}", author),
+            channel_id: ChannelId::new(1),
+            guild_id: Some(GuildId::new(guild)),
+            message_id: None,
+            content: content.to_string(),
+            attachments: vec![],
+        }
+    }
+
+    // Takes everything that was sent so far
+    pub fn take(&self) -> Vec<Outgoing> {
+        std::mem::take(&mut *self.outbox.lock().unwrap())
+    }
+
+    fn push(&self, out: Outgoing) {
+        if self.echo {
+            println!("{}", out);
+        }
+
+        self.outbox.lock().unwrap().push(out);
+    }
+}
+
+#[async_trait]
+impl Gateway for MemoryGateway {
+
+    async fn send(&self, channel: ChannelId, content: String) -> Result<MessageId, RinrError> {
+        self.push(Outgoing::Message(channel, content));
+        Ok(MessageId::new(BOT_MESSAGES + self.sent.fetch_add(1, Ordering::Relaxed)))
+    }
+
+    async fn edit(&self, channel: ChannelId, message: MessageId, content: String) -> Result<(), RinrError> {
+        self.push(Outgoing::Edit(channel, message, content));
+        Ok(())
+    }
+
+    async fn sendFile(&self, channel: ChannelId, path: String, _filename: String, content: String) -> Result<(), RinrError> {
+        self.push(Outgoing::File(channel, path, content));
+        Ok(())
+    }
+
+    async fn delete(&self, channel: ChannelId, message: MessageId) -> Result<(), RinrError> {
+        self.push(Outgoing::Delete(channel, message));
+        Ok(())
+    }
+
+    async fn dm(&self, user: UserId, content: String) -> Result<(), RinrError> {
+        self.push(Outgoing::Dm(user, content));
+        Ok(())
+    }
+
+    async fn member(&self, guild: GuildId, user: UserId) -> Result<Option<MemberInfo>, RinrError> {
+        Ok(self.members.lock().unwrap().get(&(guild, user)).cloned())
+    }
+
+    async fn memberNames(&self, guild: GuildId) -> Result<HashMap<UserId, String>, RinrError> {
+        Ok(self.members.lock().unwrap().iter().filter(|((g, _), _)| *g == guild).map(|((_, u), m)| (*u, m.name.clone())).collect())
+    }
+
+    async fn voiceChannel(&self, guild: GuildId, user: UserId) -> Result<Option<ChannelId>, RinrError> {
+        Ok(self.voice.lock().unwrap().get(&(guild, user)).copied())
+    }
+
+    async fn voiceCount(&self, guild: GuildId, channel: ChannelId) -> Result<usize, RinrError> {
+        Ok(self.voice.lock().unwrap().iter().filter(|((g, _), c)| *g == guild && **c == channel).count())
+    }
+
+    async fn play(&self, guild: GuildId, channel: ChannelId, path: String, volume: f32) -> Result<(), RinrError> {
+        self.push(Outgoing::Play(guild, channel, path, volume));
+        self.bot_voice.lock().unwrap().insert(guild);
+        Ok(())
+    }
+
+    async fn stop(&self, guild: GuildId) -> Result<bool, RinrError> {
+        self.push(Outgoing::Stop(guild));
+        Ok(self.bot_voice.lock().unwrap().contains(&guild))
+    }
+
+    async fn leave(&self, guild: GuildId) -> Result<bool, RinrError> {
+        self.push(Outgoing::Leave(guild));
+        Ok(self.bot_voice.lock().unwrap().remove(&guild))
+    }
+}
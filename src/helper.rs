@@ -1,17 +1,22 @@
-use std::{collections::{HashMap, HashSet}, env, fmt::Debug, fs::File, io::{BufRead, BufReader, Lines, Read}, path::Path, str::FromStr, sync::mpsc::Sender};
+use std::{collections::{HashMap, HashSet}, env, fmt::Debug, fs::File, io::{BufRead, BufReader, Lines, Read}, path::Path, str::FromStr, sync::{mpsc::Sender, Arc, Mutex}};
 
 use chrono::{NaiveDate, NaiveTime, Timelike};
-use serenity::{all::{ChannelId, UserId}, model::prelude::Message, prelude::{Context, TypeMapKey}};
+use serenity::{all::{ChannelId, GuildId, UserId}, prelude::{RwLock, TypeMap, TypeMapKey}};
 
 use strum::Display;
-use tokio::{fs::{create_dir_all, File as aFile}, io::AsyncWriteExt};
+use tokio::{fs::{create_dir_all, File as aFile}, io::AsyncWriteExt, sync::Mutex as aMutex};
 
 use serde::{Deserialize, Serialize};
 
 use crate::predict::UserPrediction;
 use crate::event::Command as RinrCommand;
+use crate::lang::Lang;
+use crate::error::RinrError;
 
 
+// Held while the config file is written, so two writes can't finish in the wrong order
+static CONFIG_FILE: aMutex<()> = aMutex::const_new(());
+
 
 
 
@@ -38,8 +43,9 @@ pub struct DailyEvent {
     pub interval: Timeslice,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Display, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, Display, PartialEq, Eq, Default)]
 pub enum Timeslice {
+    #[default]
     Daily,
     Weekly,
     Monthly,
@@ -53,6 +59,7 @@ pub struct EventSignal {
     pub event_type: RinrCommand,
     pub event_info: Option<DailyEvent>,
     pub channel_id: ChannelId,
+    pub guild_id: Option<GuildId>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -62,6 +69,112 @@ pub struct RinrOptions {
     pub id_counter: u64,
     reserved2: u64,
     reserved3: u64,
+    #[serde(default)]
+    pub permissions: HashMap<String, PermissionRule>,   // Keyed by command or command and mode, like "event delete"
+    #[serde(default)]
+    pub cooldowns: HashMap<String, CooldownRule>,       // Keyed by command
+    #[serde(default = "defaultConcurrency")]
+    pub concurrency: usize,                             // How many downloads/fortnite lookups may run at once
+    #[serde(default)]
+    pub language: Lang,                                 // Used by guilds without their own setting
+    #[serde(default)]
+    pub guilds: HashMap<u64, GuildOptions>,             // Keyed by guild id
+    #[serde(default)]
+    pub bot_guild: Option<u64>,                         // Guild of the bot channel, decides the language of events
+    #[serde(default)]
+    pub loudness: LoudnessOptions,                      // Every new intro is normalized to this
+    #[serde(default)]
+    pub queue: QueueOptions,
+}
+
+// Settings that only apply to one guild
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct GuildOptions {
+    #[serde(default)]
+    pub language: Option<Lang>,
+    #[serde(default)]
+    pub volume: Option<u64>,                            // Playback volume in percent, 100 if not set
+    #[serde(default)]
+    pub sounds: SoundOptions,
+    #[serde(default)]
+    pub policy: IntroPolicy,
+}
+
+// Which sounds play in a guild and how outros behave
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SoundOptions {
+    #[serde(default = "defaultOn")]
+    pub intros: bool,
+    #[serde(default = "defaultOn")]
+    pub outros: bool,
+    #[serde(default = "defaultOn")]
+    pub moves: bool,
+    #[serde(default)]
+    pub outro_delay: u64,                               // ms to wait after someone left before their outro plays
+    #[serde(default)]
+    pub outro_when_empty: bool,                         // Plays outros even if nobody is left to hear them
+}
+
+// Where and when sounds may play in a guild, checked for intros, outros and move sounds alike
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct IntroPolicy {
+    #[serde(default)]
+    pub allowed_channels: Vec<u64>,                     // Sounds only play in these voice channels if any are listed
+    #[serde(default)]
+    pub denied_channels: Vec<u64>,
+    #[serde(default)]
+    pub quiet_hours: Vec<QuietHours>,
+    #[serde(default)]
+    pub rejoin_cooldown: u64,                           // Seconds before the next sound of the same user may play
+    #[serde(default)]
+    pub min_listeners: usize,                           // Other people that have to be in the channel, bots don't count
+}
+
+// Nothing plays from start until end, a window that ends before it starts goes past midnight
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuietHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+// EBU R128 loudness in LUFS and the true peak limit in dBTP, handed to ffmpeg's loudnorm filter
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoudnessOptions {
+    #[serde(default = "defaultLoudness")]
+    pub target: f64,
+    #[serde(default = "defaultTruePeak")]
+    pub true_peak: f64,
+}
+
+// How many sounds may wait in a guild while something is playing and for how long
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueOptions {
+    #[serde(default = "defaultQueueLength")]
+    pub max_length: usize,
+    #[serde(default = "defaultQueueWait")]
+    pub max_wait: u64,                                  // Seconds, sounds that waited longer are dropped
+}
+
+// Seconds between two uses of a command, 0 disables a scope
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CooldownRule {
+    #[serde(default)]
+    pub user: u64,
+    #[serde(default)]
+    pub channel: u64,
+    #[serde(default)]
+    pub guild: u64,
+}
+
+// Anyone matching one of the lists is allowed, permissions are discord flag names like "MANAGE_GUILD"
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct PermissionRule {
+    #[serde(default)]
+    pub permissions: Vec<String>,
+    #[serde(default)]
+    pub roles: Vec<u64>,
+    #[serde(default)]
+    pub users: Vec<u64>,
 }
 
 pub struct DailyEventSignalKey;
@@ -86,11 +199,6 @@ impl Default for DailyEvent {
     }
 }
 
-impl Default for Timeslice {
-    fn default() -> Timeslice {
-        Timeslice::Daily
-    }
-}
 
 impl FromStr for Timeslice {
 
@@ -108,8 +216,9 @@ impl FromStr for Timeslice {
     }
 }
 
+// Shared between the event thread and the handlers
 impl TypeMapKey for RinrOptions {
-    type Value = HashMap<u64, RinrOptions>;
+    type Value = Arc<Mutex<RinrOptions>>;
 }
 
 impl Default for RinrOptions {
@@ -120,10 +229,108 @@ impl Default for RinrOptions {
             id_counter: 0,
             reserved2: 0,
             reserved3: 0,
+            permissions: HashMap::new(),
+            cooldowns: HashMap::new(),
+            concurrency: defaultConcurrency(),
+            language: Lang::default(),
+            guilds: HashMap::new(),
+            bot_guild: None,
+            loudness: LoudnessOptions::default(),
+            queue: QueueOptions::default(),
+        }
+    }
+}
+
+impl Default for SoundOptions {
+    fn default() -> SoundOptions {
+        SoundOptions {
+            intros: true,
+            outros: true,
+            moves: true,
+            outro_delay: 0,
+            outro_when_empty: false,
+        }
+    }
+}
+
+impl Default for LoudnessOptions {
+    fn default() -> LoudnessOptions {
+        LoudnessOptions {
+            target: defaultLoudness(),
+            true_peak: defaultTruePeak(),
+        }
+    }
+}
+
+impl Default for QueueOptions {
+    fn default() -> QueueOptions {
+        QueueOptions {
+            max_length: defaultQueueLength(),
+            max_wait: defaultQueueWait(),
+        }
+    }
+}
+
+impl RinrOptions {
+    pub fn languageFor(&self, guild: Option<u64>) -> Lang {
+        guild.and_then(|g| self.guilds.get(&g))
+             .and_then(|g| g.language)
+             .unwrap_or(self.language)
+    }
+
+    pub fn soundsFor(&self, guild: u64) -> SoundOptions {
+        self.guilds.get(&guild)
+            .map(|g| g.sounds.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn volumeFor(&self, guild: u64) -> u64 {
+        self.guilds.get(&guild)
+            .and_then(|g| g.volume)
+            .unwrap_or(100)
+    }
+
+    pub fn policyFor(&self, guild: u64) -> IntroPolicy {
+        self.guilds.get(&guild)
+            .map(|g| g.policy.clone())
+            .unwrap_or_default()
+    }
+}
+
+impl QuietHours {
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        match self.start <= self.end {
+            true => time >= self.start && time < self.end,
+            false => time >= self.start || time < self.end,
         }
     }
 }
 
+fn defaultConcurrency() -> usize {
+    2
+}
+
+fn defaultOn() -> bool {
+    true
+}
+
+// Louder than the -23 of broadcast, intros have to get through people talking over them
+fn defaultLoudness() -> f64 {
+    -16.0
+}
+
+fn defaultTruePeak() -> f64 {
+    -1.5
+}
+
+fn defaultQueueLength() -> usize {
+    5
+}
+
+fn defaultQueueWait() -> u64 {
+    30
+}
+
 pub trait States {
     fn insert(&mut self, event: DailyEvent);
 
@@ -133,14 +340,14 @@ pub trait States {
 
     fn removeEntry(&mut self, num: u64); // by name or id
 
-    fn setChannel(&mut self, num: u64);
+    fn setChannel(&mut self, num: u64, guild: Option<u64>);
 
-    fn resortEvents(&mut self);
+    fn resortEvents(&mut self, curr_time: NaiveTime);
 
     fn _printElements(&self);
 }
 
-impl <'a> States for &'a mut RinrOptions {
+impl States for &mut RinrOptions {
     
     fn insert(&mut self, mut event: DailyEvent) {
         
@@ -167,7 +374,7 @@ impl <'a> States for &'a mut RinrOptions {
             }
         }
 
-        return false;
+        false
     }
 
     fn unsubscribe(&mut self, event_data: DailyEvent) -> bool {
@@ -183,20 +390,19 @@ impl <'a> States for &'a mut RinrOptions {
             }
         }
 
-        return false;
+        false
     }
 
     fn removeEntry(&mut self, num: u64) {
         self.events.retain(|x| x.id != num);
     }
 
-    fn setChannel(&mut self, num: u64) {
+    fn setChannel(&mut self, num: u64, guild: Option<u64>) {
         self.bot_channel = Some(num);
+        self.bot_guild = guild;
     }
 
-    fn resortEvents(&mut self) {
-        let curr_time: NaiveTime = chrono::offset::Local::now().time();
-
+    fn resortEvents(&mut self, curr_time: NaiveTime) {
         self.events.sort_by_key(|e| curr_time.timeDif(&e.timestamp));
     }
 
@@ -287,17 +493,15 @@ pub fn fillStruct() -> HashMap<u64, UserPrediction> {
     let reader: BufReader<File> = BufReader::new(file);
     let lines: Lines<BufReader<File>> = reader.lines();
 
-    for line in lines {
-       if let Ok(text) = line {
-            let vec: Vec<&str> = text.as_str().split("=").collect();
-            if vec.len() != 4 {return map};
+    for text in lines.map_while(Result::ok) {
+        let vec: Vec<&str> = text.as_str().split("=").collect();
+        if vec.len() != 4 {return map};
 
-            let pre: UserPrediction = UserPrediction{   user_id: vec[1].parse::<u64>().expect("Couldn't parse u64"),
-                                                        prediction: vec[2].parse::<String>().expect("Couldn't parse String"),
-                                                        user_name: vec[3].parse::<String>().expect("Couldn't parse String ")};
+        let pre: UserPrediction = UserPrediction{   user_id: vec[1].parse::<u64>().expect("Couldn't parse u64"),
+                                                    prediction: vec[2].parse::<String>().expect("Couldn't parse String"),
+                                                    user_name: vec[3].parse::<String>().expect("Couldn't parse String ")};
 
-            map.insert(vec[0].parse::<u64>().expect("Couldn't Parse u64"), pre);
-        };
+        map.insert(vec[0].parse::<u64>().expect("Couldn't Parse u64"), pre);
     }
 
     map
@@ -318,6 +522,16 @@ pub fn buildVidPath(name: String) -> String {
     current
 }
 
+//--------------------------------------------------------------------------------------------------------------------------
+// Creates path for the folder of the sounds
+pub fn buildVidDir() -> String {
+
+    let mut current: String = env::current_dir().expect("Unable to get current directory!").to_str().unwrap().to_string();
+    current.push_str("\\src\\vid\\");
+
+    current
+}
+
 //--------------------------------------------------------------------------------------------------------------------------
 // Checks if directories exist and creates them if not (first time startup)
 pub async fn checkDirs() {
@@ -351,19 +565,11 @@ pub async fn checkDirs() {
 
 
 //--------------------------------------------------------------------------------------------------------------------------
-// Writes Message to provided channel
-pub async fn say(msg: &Message, ctx: &Context, out: String) {
-    if let Err(why) = msg.channel_id.say(&ctx.http, out).await {
-        println!("Send Message failed. Error: {:?}", why)
-    }
-}
-
-
-//--------------------------------------------------------------------------------------------------------------------------
-// Deletes Message in provided channel
-pub async fn delete(msg: &Message, ctx: &Context) {
-    if let Err(why) = msg.delete(&ctx.http).await {
-        println!("Delete Message failed Error: {:?}", why)
+// Gets the configured language of a guild
+pub async fn guildLang(data: &RwLock<TypeMap>, guild_id: Option<GuildId>) -> Lang {
+    match data.read().await.get::<RinrOptions>() {
+        Some(C) => C.lock().unwrap().languageFor(guild_id.map(|g| g.get())),
+        None => Lang::default(),
     }
 }
 
@@ -392,19 +598,21 @@ pub async fn findTimerPath() -> Option<String> {
 }
 
 //--------------------------------------------------------------------------------------------------------------------------
-// Stupid ass time formatter
-pub fn formatSec(secs: u64) -> String {
-    let hours: u64 = secs / 3600;
-    let minutes: u64 = secs / 60;
-    let seconds: u64 = secs % 60;
+// Writes the shared config, every change to it is saved through here
+// The copy is taken once it is this call's turn, so an older copy can never overwrite a newer one
+pub async fn saveConfig(config: &Mutex<RinrOptions>) -> Result<(), RinrError> {
+
+    let _file = CONFIG_FILE.lock().await;
 
-    format!("{}:{:02}:{:02}", hours, minutes, seconds)
-} 
+    let snapshot: RinrOptions = config.lock().unwrap().clone();
+
+    writeConfig(Some(&snapshot)).await
+}
 
 
 //--------------------------------------------------------------------------------------------------------------------------
 // Write to Config
-pub async fn writeConfig(data: Option<&RinrOptions>) {
+async fn writeConfig(data: Option<&RinrOptions>) -> Result<(), RinrError> {
 
     let mut path: String = env::current_dir().expect("Unable to get current directory!").to_str().unwrap().to_string();
     let folder: &str = "\\src\\config\\";
@@ -415,28 +623,28 @@ pub async fn writeConfig(data: Option<&RinrOptions>) {
     let p: &Path = Path::new(path.as_str());
 
     if !p.exists() {
-        create_dir_all(&path).await.expect("Unable to create folder ./src/config/");
+        create_dir_all(&path).await?;
         println!("Created Config Directory!");
     }
 
     path.push_str(end);
 
-    let mut file: aFile = match aFile::create(&path).await {
-        Ok(F) => F,
-        _ => aFile::create(&path).await.expect("File doesn't exist and is unable to be created!"),
-    };
+    let json: String = match data {
+        Some(R) => serde_json::to_string(R),
+        None => serde_json::to_string(&createDefaultConfig()),
+    }.map_err(|e| RinrError::Storage(format!("Unable to serialize config: {}", e)))?;
 
     println!("path: {}", path);
-    println!("{}", serde_json::to_string(data.unwrap()).unwrap());
+    println!("{}", json);
 
-    let _ = match data {
-        Some(R) => file.write_all(serde_json::to_string(R).unwrap().as_bytes()).await.expect("Unable to serialize config!"),
-        None => file.write_all(serde_json::to_string(&createDefaultConfig()).unwrap().as_bytes()).await.expect("Unable to serialize default config!"),
-    };
-    
-    let _ = file.flush().await;
+    let mut file: aFile = aFile::create(&path).await?;
+
+    file.write_all(json.as_bytes()).await?;
+    file.flush().await?;
 
     println!("Wrote Config!");
+
+    Ok(())
 }
 
 
@@ -0,0 +1,1163 @@
+/*
+    Intro collection of every user
+
+    a user can keep several intros under a name and picks how one of them is chosen when they join a channel
+    the sound files stay in ./src/vid/ as <user_id>_<name>.mp3, the intro called default keeps the old <user_id>.mp3
+    so files from before collections existed are picked up as the default intro
+    the clip an intro was rendered from is kept as <file>.source.mp3, see effects.rs
+    outros and move sounds are kept the same way in their own collections, as <user_id>.outro.<name>.mp3 and <user_id>.move.<name>.mp3
+    every new version of an intro gets its own files with .v<n> at the end, the older versions stay around for $intro rollback
+    until there are more than MAX_VERSIONS of them, so a failed $setvideo never touches the intro that is playing
+*/
+use std::collections::HashMap;
+use std::{env, fs, fs::File, io::Read, path::Path, str::FromStr};
+
+use chrono::NaiveDateTime;
+use rand::Rng;
+use rand::distributions::WeightedIndex;
+use serde::{Deserialize, Serialize};
+use serenity::all::{ChannelId, GuildId, UserId};
+use serenity::prelude::{RwLock, TypeMap, TypeMapKey};
+use tokio::{fs::{create_dir_all, File as aFile}, io::AsyncWriteExt};
+
+use crate::args::{formatTimestamp, ArgError, ArgReason, Expected, ParsedArgs};
+use crate::error::RinrError;
+use crate::gateway::Invocation;
+use crate::helper::{buildVidDir, buildVidPath, checkVidPath, removeUserAt};
+use crate::queue::queueSound;
+use crate::lang::{tr, Lang};
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Const Declaration
+
+pub const DEFAULT_NAME: &str = "default";
+pub const MAX_NAME: usize = 32;
+pub const MAX_INTROS: usize = 10;
+pub const MAX_VERSIONS: usize = 5;     // Older versions kept per intro
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Enum Declaration
+
+// When a sound is played, every kind has its own collection, strategy and files
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SoundKind {
+    #[default]
+    Intro,          // Joining a channel
+    Outro,          // Leaving, played in the channel that was left
+    Move,           // Switching to another channel
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Strategy {
+    #[default]
+    Fixed,          // Always the selected intro, the newest one if nothing was selected
+    Random,
+    RoundRobin,
+    Weighted,       // Random, but intros with a higher weight come up more often
+}
+
+impl FromStr for Strategy {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<Strategy, Self::Err> {
+        match input.to_lowercase().as_str() {
+            "fixed" => Ok(Strategy::Fixed),
+            "random" => Ok(Strategy::Random),
+            "roundrobin" | "rotate" => Ok(Strategy::RoundRobin),
+            "weighted" => Ok(Strategy::Weighted),
+            _ => Err(()),
+        }
+    }
+}
+
+impl FromStr for SoundKind {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<SoundKind, Self::Err> {
+        match input.to_lowercase().as_str() {
+            "intro" | "join" => Ok(SoundKind::Intro),
+            "outro" | "leave" => Ok(SoundKind::Outro),
+            "move" => Ok(SoundKind::Move),
+            _ => Err(()),
+        }
+    }
+}
+
+impl SoundKind {
+    pub fn describe(&self, lang: Lang) -> String {
+        match self {
+            SoundKind::Intro => tr(lang, "sound.intro", &[]),
+            SoundKind::Outro => tr(lang, "sound.outro", &[]),
+            SoundKind::Move => tr(lang, "sound.move", &[]),
+        }
+    }
+}
+
+impl Strategy {
+    fn describe(&self, lang: Lang) -> String {
+        match self {
+            Strategy::Fixed => tr(lang, "intro.strategy_fixed", &[]),
+            Strategy::Random => tr(lang, "intro.strategy_random", &[]),
+            Strategy::RoundRobin => tr(lang, "intro.strategy_roundrobin", &[]),
+            Strategy::Weighted => tr(lang, "intro.strategy_weighted", &[]),
+        }
+    }
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Struct Declaration
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntroSound {
+    pub name: String,
+    pub file: String,               // File name in ./src/vid/ without the extension
+    #[serde(default = "defaultWeight")]
+    pub weight: u32,
+    #[serde(default)]
+    pub effects: String,            // ffmpeg filter chain the intro was rendered with, empty for none
+    #[serde(default)]
+    pub info: Option<IntroInfo>,    // None for intros from before it was kept
+    #[serde(default)]
+    pub version: u32,               // 0 for the first one, which has the plain file name
+    #[serde(default)]
+    pub history: Vec<IntroVersion>, // Older versions, the newest first
+}
+
+// Names of the sounds of a user by kind
+pub type SoundNames = Vec<(SoundKind, Vec<String>)>;
+
+// An older version of an intro that can be brought back
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntroVersion {
+    pub file: String,
+    pub effects: String,
+    pub info: Option<IntroInfo>,
+    pub version: u32,
+}
+
+// Where an intro came from and who set it, times in ms
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntroInfo {
+    pub source: String,             // Link or the name of the uploaded file
+    pub title: String,
+    pub start: u64,
+    pub length: u64,                // Of the clip
+    pub duration: u64,              // Of the whole video or file
+    pub set_by: u64,
+    pub set_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UserIntros {
+    pub sounds: Vec<IntroSound>,
+    #[serde(default)]
+    pub strategy: Strategy,
+    #[serde(default)]
+    pub selected: Option<String>,   // Used by Fixed
+    #[serde(default)]
+    pub next: usize,                // Used by RoundRobin
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IntroLibrary {
+    pub users: HashMap<u64, UserIntros>,    // Intros, the name is from before there were other kinds
+    #[serde(default)]
+    pub outros: HashMap<u64, UserIntros>,
+    #[serde(default)]
+    pub moves: HashMap<u64, UserIntros>,
+}
+
+impl TypeMapKey for IntroLibrary {
+    type Value = IntroLibrary;
+}
+
+fn defaultWeight() -> u32 {
+    1
+}
+
+
+impl IntroLibrary {
+
+    // Gets the sounds of a user, an old single intro is taken over as the default one
+    pub fn entry(&mut self, kind: SoundKind, user_id: u64) -> &mut UserIntros {
+
+        let sounds: &mut HashMap<u64, UserIntros> = match kind {
+            SoundKind::Intro => &mut self.users,
+            SoundKind::Outro => return self.outros.entry(user_id).or_default(),
+            SoundKind::Move => return self.moves.entry(user_id).or_default(),
+        };
+
+        sounds.entry(user_id).or_insert_with(|| {
+
+            let mut intros: UserIntros = UserIntros::default();
+            let file: String = introFile(kind, user_id, DEFAULT_NAME);
+
+            if checkVidPath(&buildVidPath(file.clone())) {
+                intros.sounds.push(IntroSound { name: DEFAULT_NAME.to_string(), file, weight: defaultWeight(), effects: String::new(), info: None, version: 0, history: vec![] });
+            }
+
+            intros
+        })
+    }
+
+    // The names of all sounds of a user by kind, without creating entries for users that have none
+    pub fn names(&self, user_id: u64) -> SoundNames {
+
+        [(SoundKind::Intro, &self.users), (SoundKind::Outro, &self.outros), (SoundKind::Move, &self.moves)]
+            .into_iter()
+            .filter_map(|(kind, sounds)| sounds.get(&user_id).map(|i| (kind, i.sounds.iter().map(|s| s.name.clone()).collect::<Vec<String>>())))
+            .filter(|(_, names)| !names.is_empty())
+            .collect()
+    }
+}
+
+
+impl IntroSound {
+
+    fn snapshot(&self) -> IntroVersion {
+        IntroVersion { file: self.file.clone(), effects: self.effects.clone(), info: self.info.clone(), version: self.version }
+    }
+
+    // The version a new file of this intro gets, rolled back intros can be older than their history
+    pub fn nextVersion(&self) -> u32 {
+        self.history.iter().map(|v| v.version).fold(self.version, u32::max) + 1
+    }
+
+    // Replaces this version with a newer one and keeps this one in the history, the name and weight stay as the user set them
+    // Gives back the versions that fell out of the history
+    pub fn supersede(&mut self, newer: IntroVersion) -> Vec<IntroVersion> {
+
+        self.history.insert(0, self.snapshot());
+
+        let dropped: Vec<IntroVersion> = self.history.split_off(self.history.len().min(MAX_VERSIONS));
+
+        self.file = newer.file;
+        self.effects = newer.effects;
+        self.info = newer.info;
+        self.version = newer.version;
+
+        dropped
+    }
+
+    // Brings back the nth older version, 1 being the one before this, this one goes into the history instead
+    pub fn rollback(&mut self, n: usize) -> Option<IntroVersion> {
+
+        if n == 0 || n > self.history.len() {
+            return None;
+        }
+
+        let older: IntroVersion = self.history.remove(n - 1);
+        self.history.insert(0, self.snapshot());
+
+        self.file = older.file.clone();
+        self.effects = older.effects.clone();
+        self.info = older.info.clone();
+        self.version = older.version;
+
+        Some(older)
+    }
+}
+
+
+impl UserIntros {
+
+    pub fn find(&self, name: &str) -> Option<&IntroSound> {
+        self.sounds.iter().find(|s| s.name == name)
+    }
+
+    // The selected intro, the newest one if nothing was selected
+    pub fn current(&self) -> Option<&IntroSound> {
+        self.selected.as_ref()
+                     .and_then(|n| self.find(n))
+                     .or(self.sounds.last())
+    }
+
+    // Adds an intro or makes it the new version of the one with the same name, a fixed selection moves to it like before collections
+    // Gives back the versions that fell out of the history, their files aren't needed anymore
+    pub fn add(&mut self, sound: IntroSound) -> Vec<IntroVersion> {
+
+        self.selected = Some(sound.name.clone());
+
+        match self.sounds.iter_mut().find(|s| s.name == sound.name) {
+            Some(S) => S.supersede(sound.snapshot()),
+            None => {
+                self.sounds.push(sound);
+                vec![]
+            },
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<IntroSound> {
+
+        let pos: usize = self.sounds.iter().position(|s| s.name == name)?;
+
+        if self.selected.as_deref() == Some(name) {
+            self.selected = None;
+        }
+
+        Some(self.sounds.remove(pos))
+    }
+
+    // Chooses the intro to play with the strategy of the user
+    pub fn pick(&mut self) -> Option<&IntroSound> {
+
+        if self.sounds.is_empty() {
+            return None;
+        }
+
+        let index: usize = match self.strategy {
+            Strategy::Fixed => return self.current(),
+            Strategy::Random => rand::thread_rng().gen_range(0..self.sounds.len()),
+            Strategy::RoundRobin => {
+                let index: usize = self.next % self.sounds.len();
+                self.next = index + 1;
+                index
+            },
+            Strategy::Weighted => match WeightedIndex::new(self.sounds.iter().map(|s| s.weight)) {
+                Ok(W) => rand::thread_rng().sample(W),
+                Err(_) => 0,
+            },
+        };
+
+        self.sounds.get(index)
+    }
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Matches the subversions of the intro command
+pub async fn introHandler(inv: &Invocation, args: &ParsedArgs) -> Result<(), RinrError> {
+
+    match args.text("mode").unwrap_or("") {
+        "list" => listIntros(inv, args).await,
+        "info" => showInfo(inv, args).await,
+        "history" => showHistory(inv, args).await,
+        "rollback" => rollbackIntro(inv, args).await,
+        "remove" => removeIntro(inv, args).await,
+        "select" => selectIntro(inv, args).await,
+        "weight" => weighIntro(inv, args).await,
+        "strategy" => setStrategy(inv, args).await,
+        "preview" => previewIntro(inv, args).await,
+        "file" => uploadIntro(inv, args).await,
+        _ => Ok(()),
+    }
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Lists the intros of a mentioned user, or everyone with sounds on the server if nobody is mentioned
+async fn listIntros(inv: &Invocation, args: &ParsedArgs) -> Result<(), RinrError> {
+
+    let user: UserId = match (args.user("user"), inv.guild_id) {
+        (Some(U), _) => U,
+        (None, Some(_)) => return listGuild(inv).await,
+        (None, None) => inv.author,
+    };
+
+    let lang: Lang = inv.lang().await;
+    let kind: SoundKind = soundKind(args);
+
+    let intros: UserIntros = {
+        let mut u_data = inv.data.write().await;
+        u_data.get_mut::<IntroLibrary>().unwrap().entry(kind, user.get()).clone()
+    };
+
+    if intros.sounds.is_empty() {
+        match user == inv.author {
+            true => inv.reply("intro.empty", &[]).await,
+            false => inv.reply("intro.empty_other", &[("user", &format!("<@{}>", user))]).await,
+        }
+        return Ok(());
+    }
+
+    let name: String = match user == inv.author {
+        true => inv.author_name.clone(),
+        false => format!("<@{}>", user),
+    };
+
+    let mut out: String = tr(lang, "intro.list_title", &[("kind", &kind.describe(lang)), ("user", &name), ("strategy", &intros.strategy.describe(lang))]);
+
+    for s in &intros.sounds {
+        out.push_str(&format!("\n`{}`", s.name));
+
+        match intros.strategy {
+            Strategy::Weighted => out.push_str(&format!(" ({})", tr(lang, "intro.list_weight", &[("weight", &s.weight.to_string())]))),
+            Strategy::Fixed if intros.selected.as_deref() == Some(s.name.as_str()) => out.push_str(&format!(" ({})", tr(lang, "intro.list_selected", &[]))),
+            _ => (),
+        }
+    }
+
+    inv.say(out).await;
+
+    Ok(())
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Lists everyone on the server that has sounds, with the names of them
+pub async fn listGuild(inv: &Invocation) -> Result<(), RinrError> {
+
+    let guild_id: GuildId = match inv.guild_id {
+        Some(G) => G,
+        None => return Ok(()),
+    };
+
+    // Old single intros only get into the library once they are used, so they are taken over from the folder here
+    let legacy: Vec<u64> = legacyIntros();
+
+    // Sorted by user so the list doesn't jump around between calls
+    let mut users: Vec<(u64, SoundNames)> = {
+        let mut u_data = inv.data.write().await;
+        let library: &mut IntroLibrary = u_data.get_mut::<IntroLibrary>().unwrap();
+
+        for id in legacy {
+            library.entry(SoundKind::Intro, id);
+        }
+
+        let mut ids: Vec<u64> = library.users.keys().chain(library.outros.keys()).chain(library.moves.keys()).copied().collect();
+        ids.sort();
+        ids.dedup();
+
+        ids.into_iter()
+           .map(|id| (id, library.names(id)))
+           .filter(|(_, sounds)| !sounds.is_empty())
+           .collect()
+    };
+
+    let lang: Lang = inv.lang().await;
+    let mut lines: Vec<String> = vec![];
+
+    // Only members of this server are listed, the library is shared between all of them
+    let members: HashMap<UserId, String> = inv.gateway.memberNames(guild_id).await?;
+
+    for (id, sounds) in users.drain(..) {
+
+        let name: String = match members.get(&UserId::new(id)) {
+            Some(N) => N.clone(),
+            None => continue,
+        };
+
+        let sounds: Vec<String> = sounds.iter()
+                                        .map(|(kind, names)| {
+                                            let names: String = names.iter().map(|n| format!("`{}`", n)).collect::<Vec<String>>().join(", ");
+                                            format!("{} {}", kind.describe(lang), names)
+                                        })
+                                        .collect();
+
+        lines.push(format!("**{}**: {}", name, sounds.join(" · ")));
+    }
+
+    if lines.is_empty() {
+        inv.reply("intro.list_guild_empty", &[]).await;
+        return Ok(());
+    }
+
+    lines.insert(0, tr(lang, "intro.list_guild_title", &[]));
+    inv.sayLines(lines).await;
+
+    Ok(())
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Ids of the users that still have an intro from before the library, those files are named after the id alone
+fn legacyIntros() -> Vec<u64> {
+
+    let entries: fs::ReadDir = match fs::read_dir(buildVidDir()) {
+        Ok(E) => E,
+        Err(_) => return vec![],
+    };
+
+    entries.filter_map(|e| e.ok())
+           .filter_map(|e| e.file_name().to_str()?.strip_suffix(".mp3")?.parse::<u64>().ok())
+           .collect()
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Shows where an intro came from, of the author or a mentioned user
+async fn showInfo(inv: &Invocation, args: &ParsedArgs) -> Result<(), RinrError> {
+
+    let user: UserId = args.user("user").unwrap_or(inv.author);
+
+    let sound: IntroSound = match chosenIntro(inv, user, args).await? {
+        Some(S) => S,
+        None => return Ok(()),
+    };
+
+    let lang: Lang = inv.lang().await;
+
+    let effects: String = match sound.effects.is_empty() {
+        true => tr(lang, "intro.info_no_effects", &[]),
+        false => format!("`{}`", sound.effects),
+    };
+
+    let info: IntroInfo = match sound.info {
+        Some(I) => I,
+        None => {
+            inv.reply("intro.info_missing", &[("name", &sound.name), ("effects", &effects)]).await;
+            return Ok(());
+        },
+    };
+
+    // Links are wrapped so discord doesn't embed them
+    let source: String = match info.source.starts_with("http") {
+        true => format!("<{}>", info.source),
+        false => format!("`{}`", info.source),
+    };
+
+    inv.reply("intro.info", &[
+        ("name", &sound.name),
+        ("user", &format!("<@{}>", user)),
+        ("title", &info.title),
+        ("source", &source),
+        ("start", &formatTimestamp(info.start)),
+        ("end", &formatTimestamp(info.start + info.length)),
+        ("duration", &formatTimestamp(info.duration)),
+        ("effects", &effects),
+        ("setter", &format!("<@{}>", info.set_by)),
+        ("date", &info.set_at.format("%d/%m/%Y %H:%M").to_string()),
+    ]).await;
+
+    Ok(())
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Lists the older versions of an intro that can be brought back
+async fn showHistory(inv: &Invocation, args: &ParsedArgs) -> Result<(), RinrError> {
+
+    let sound: IntroSound = match chosenIntro(inv, inv.author, args).await? {
+        Some(S) => S,
+        None => return Ok(()),
+    };
+
+    let lang: Lang = inv.lang().await;
+
+    if sound.history.is_empty() {
+        inv.reply("intro.history_empty", &[("name", &sound.name)]).await;
+        return Ok(());
+    }
+
+    let mut out: String = tr(lang, "intro.history_title", &[("name", &sound.name)]);
+    out.push_str(&format!("\n{}", tr(lang, "intro.history_current", &[("entry", &describeVersion(lang, &sound.effects, &sound.info))])));
+
+    for (i, version) in sound.history.iter().enumerate() {
+        out.push_str(&format!("\n`{}` {}", i + 1, describeVersion(lang, &version.effects, &version.info)));
+    }
+
+    inv.say(out).await;
+
+    Ok(())
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Brings back an older version of an intro of the author, the one that was playing goes into the history
+async fn rollbackIntro(inv: &Invocation, args: &ParsedArgs) -> Result<(), RinrError> {
+
+    // $intro rollback 2 goes back two versions of the selected intro, $intro rollback <name> 2 of a named one
+    let (name, n): (Option<String>, i64) = match (args.text("name"), args.int("version")) {
+        (Some(N), None) if N.parse::<i64>().is_ok() => (None, N.parse::<i64>().unwrap()),
+        (Some(N), V) => (Some(checkName(N)?), V.unwrap_or(1)),
+        (None, V) => (None, V.unwrap_or(1)),
+    };
+
+    let kind: SoundKind = soundKind(args);
+
+    let (result, snapshot): (Result<(String, IntroVersion), Option<String>>, IntroLibrary) = {
+        let mut u_data = inv.data.write().await;
+        let library: &mut IntroLibrary = u_data.get_mut::<IntroLibrary>().unwrap();
+        let intros: &mut UserIntros = library.entry(kind, inv.author.get());
+
+        let name: Option<String> = name.or(intros.current().map(|s| s.name.clone()));
+
+        let result: Result<(String, IntroVersion), Option<String>> = match intros.sounds.iter_mut().find(|s| Some(&s.name) == name.as_ref()) {
+            // A version whose file got deleted by hand can't be played anymore
+            Some(S) if n > 0 && S.history.get(n as usize - 1).is_some_and(|v| checkVidPath(&buildVidPath(v.file.clone()))) => {
+                Ok((S.name.clone(), S.rollback(n as usize).unwrap()))
+            },
+            _ => Err(name),
+        };
+
+        (result, library.clone())
+    };
+
+    let (name, version): (String, IntroVersion) = match result {
+        Ok(R) => R,
+        Err(Some(N)) => {
+            inv.reply("intro.unknown_version", &[("name", &N), ("version", &n.to_string())]).await;
+            return Ok(());
+        },
+        Err(None) => {
+            inv.reply("intro.empty", &[]).await;
+            return Ok(());
+        },
+    };
+
+    writeIntros(&snapshot).await?;
+
+    let lang: Lang = inv.lang().await;
+
+    inv.reply("intro.rolled_back", &[("name", &name), ("entry", &describeVersion(lang, &version.effects, &version.info))]).await;
+
+    Ok(())
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Describes a version of an intro in a single line for the history
+fn describeVersion(lang: Lang, effects: &str, info: &Option<IntroInfo>) -> String {
+
+    let effects: String = match effects.is_empty() {
+        true => tr(lang, "intro.info_no_effects", &[]),
+        false => format!("`{}`", effects),
+    };
+
+    match info {
+        Some(I) => tr(lang, "intro.history_entry", &[
+            ("title", &I.title),
+            ("start", &formatTimestamp(I.start)),
+            ("end", &formatTimestamp(I.start + I.length)),
+            ("effects", &effects),
+            ("date", &I.set_at.format("%d/%m/%Y %H:%M").to_string()),
+        ]),
+        None => tr(lang, "intro.history_entry_old", &[("effects", &effects)]),
+    }
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Removes an intro of the author and its file
+async fn removeIntro(inv: &Invocation, args: &ParsedArgs) -> Result<(), RinrError> {
+
+    let name: String = introName(args, "remove")?;
+
+    let (removed, snapshot): (Option<IntroSound>, IntroLibrary) = {
+        let mut u_data = inv.data.write().await;
+        let library: &mut IntroLibrary = u_data.get_mut::<IntroLibrary>().unwrap();
+
+        (library.entry(soundKind(args), inv.author.get()).remove(&name), library.clone())
+    };
+
+    let sound: IntroSound = match removed {
+        Some(S) => S,
+        None => {
+            inv.reply("intro.unknown", &[("name", &name)]).await;
+            return Ok(());
+        },
+    };
+
+    writeIntros(&snapshot).await?;
+
+    deleteSoundFiles(&sound.file);
+
+    for version in sound.history {
+        deleteSoundFiles(&version.file);
+    }
+
+    inv.reply("intro.removed", &[("name", &name)]).await;
+
+    Ok(())
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Plays an intro of the author in their voice channel without them having to join again
+async fn previewIntro(inv: &Invocation, args: &ParsedArgs) -> Result<(), RinrError> {
+
+    let guild_id: GuildId = match inv.guild_id {
+        Some(G) => G,
+        None => return Ok(()),
+    };
+
+    let sound: IntroSound = match chosenIntro(inv, inv.author, args).await? {
+        Some(S) => S,
+        None => return Ok(()),
+    };
+
+    let channel_id: ChannelId = match inv.gateway.voiceChannel(guild_id, inv.author).await? {
+        Some(C) => C,
+        None => {
+            inv.reply("intro.not_in_voice", &[]).await;
+            return Ok(());
+        },
+    };
+
+    // Waits for whatever is playing right now instead of cutting into it
+    if !queueSound(&inv.gateway, &inv.data, guild_id, channel_id, buildVidPath(sound.file)).await? {
+        inv.reply("voice.queue_full", &[]).await;
+    }
+
+    Ok(())
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Uploads the file of an intro to the channel, of the author or a mentioned user
+async fn uploadIntro(inv: &Invocation, args: &ParsedArgs) -> Result<(), RinrError> {
+
+    let user: UserId = args.user("user").unwrap_or(inv.author);
+
+    let sound: IntroSound = match chosenIntro(inv, user, args).await? {
+        Some(S) => S,
+        None => return Ok(()),
+    };
+
+    let lang: Lang = inv.lang().await;
+    let content: String = tr(lang, "intro.file", &[("name", &sound.name), ("user", &format!("<@{}>", user))]);
+
+    inv.gateway.sendFile(inv.channel_id, buildVidPath(sound.file), format!("{}.mp3", sound.name), content).await
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Gets the named intro of a user or the one they have selected, tells the author if there is none
+async fn chosenIntro(inv: &Invocation, user: UserId, args: &ParsedArgs) -> Result<Option<IntroSound>, RinrError> {
+
+    let name: Option<String> = match args.text("name") {
+        Some(N) => Some(checkName(N)?),
+        None => None,
+    };
+
+    let sound: Option<IntroSound> = {
+        let mut u_data = inv.data.write().await;
+        let intros: &mut UserIntros = u_data.get_mut::<IntroLibrary>().unwrap().entry(soundKind(args), user.get());
+
+        match &name {
+            Some(N) => intros.find(N).cloned(),
+            None => intros.current().cloned(),
+        }
+    };
+
+    // Only files that are there count, an intro could have been deleted by hand
+    if let Some(S) = sound.filter(|s| checkVidPath(&buildVidPath(s.file.clone()))) {
+        return Ok(Some(S));
+    }
+
+    match (name, user == inv.author) {
+        (Some(N), true) => inv.reply("intro.unknown", &[("name", &N)]).await,
+        (Some(N), false) => inv.reply("intro.unknown_other", &[("name", &N), ("user", &format!("<@{}>", user))]).await,
+        (None, true) => inv.reply("intro.empty", &[]).await,
+        (None, false) => inv.reply("intro.empty_other", &[("user", &format!("<@{}>", user))]).await,
+    }
+
+    Ok(None)
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Sets the intro that is played with the fixed strategy
+async fn selectIntro(inv: &Invocation, args: &ParsedArgs) -> Result<(), RinrError> {
+
+    let name: String = introName(args, "select")?;
+
+    let snapshot: Option<IntroLibrary> = {
+        let mut u_data = inv.data.write().await;
+        let library: &mut IntroLibrary = u_data.get_mut::<IntroLibrary>().unwrap();
+        let intros: &mut UserIntros = library.entry(soundKind(args), inv.author.get());
+
+        match intros.find(&name) {
+            Some(_) => {
+                intros.selected = Some(name.clone());
+                intros.strategy = Strategy::Fixed;
+                Some(library.clone())
+            },
+            None => None,
+        }
+    };
+
+    match snapshot {
+        Some(S) => {
+            writeIntros(&S).await?;
+            inv.reply("intro.selected", &[("name", &name)]).await;
+        },
+        None => inv.reply("intro.unknown", &[("name", &name)]).await,
+    }
+
+    Ok(())
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Sets the weight of an intro for the weighted strategy
+async fn weighIntro(inv: &Invocation, args: &ParsedArgs) -> Result<(), RinrError> {
+
+    let name: String = introName(args, "weight")?;
+
+    let weight: u32 = match args.int("weight") {
+        Some(W) => W as u32,
+        None => return Err(ArgError::new("weight", ArgReason::RequiredFor("weight".to_string())).into()),
+    };
+
+    let snapshot: Option<IntroLibrary> = {
+        let mut u_data = inv.data.write().await;
+        let library: &mut IntroLibrary = u_data.get_mut::<IntroLibrary>().unwrap();
+
+        match library.entry(soundKind(args), inv.author.get()).sounds.iter_mut().find(|s| s.name == name) {
+            Some(S) => {
+                S.weight = weight;
+                Some(library.clone())
+            },
+            None => None,
+        }
+    };
+
+    match snapshot {
+        Some(S) => {
+            writeIntros(&S).await?;
+            inv.reply("intro.weight_set", &[("name", &name), ("weight", &weight.to_string())]).await;
+        },
+        None => inv.reply("intro.unknown", &[("name", &name)]).await,
+    }
+
+    Ok(())
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Sets how the intro is picked when the author joins
+async fn setStrategy(inv: &Invocation, args: &ParsedArgs) -> Result<(), RinrError> {
+
+    let raw: &str = match args.text("name") {
+        Some(N) => N,
+        None => return Err(ArgError::new("name", ArgReason::RequiredFor("strategy".to_string())).into()),
+    };
+
+    let strategy: Strategy = match Strategy::from_str(raw) {
+        Ok(S) => S,
+        Err(_) => return Err(ArgError::invalid("name", raw, Expected::OneOf("fixed, random, roundrobin, weighted".to_string())).into()),
+    };
+
+    let snapshot: IntroLibrary = {
+        let mut u_data = inv.data.write().await;
+        let library: &mut IntroLibrary = u_data.get_mut::<IntroLibrary>().unwrap();
+
+        library.entry(soundKind(args), inv.author.get()).strategy = strategy;
+        library.clone()
+    };
+
+    writeIntros(&snapshot).await?;
+
+    let lang: Lang = inv.lang().await;
+    inv.say(tr(lang, "intro.strategy_set", &[("strategy", &strategy.describe(lang))])).await;
+
+    Ok(())
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Picks the intro a user gets when joining, None if they have none
+pub async fn pickIntro(data: &RwLock<TypeMap>, kind: SoundKind, user_id: u64) -> Option<String> {
+
+    let (file, snapshot): (String, Option<IntroLibrary>) = {
+        let mut u_data = data.write().await;
+        let library: &mut IntroLibrary = u_data.get_mut::<IntroLibrary>()?;
+        let intros: &mut UserIntros = library.entry(kind, user_id);
+
+        let file: String = intros.pick()?.file.clone();
+
+        // Only the rotation changes when picking
+        match intros.strategy {
+            Strategy::RoundRobin => (file, Some(library.clone())),
+            _ => (file, None),
+        }
+    };
+
+    if let Some(S) = snapshot {
+        if let Err(E) = writeIntros(&S).await {
+            println!("Unable to save intro rotation: {}", E);
+        }
+    }
+
+    Some(buildVidPath(file))
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Checks the name argument of an intro command
+pub fn introName(args: &ParsedArgs, context: &str) -> Result<String, ArgError> {
+
+    let name: &str = match args.text("name") {
+        Some(N) => N,
+        None => return Err(ArgError::new("name", ArgReason::RequiredFor(context.to_string()))),
+    };
+
+    checkName(name)
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Names end up in file names, so only a safe set of characters is allowed
+pub fn checkName(name: &str) -> Result<String, ArgError> {
+
+    let valid: bool = !name.is_empty()
+                   && name.len() <= MAX_NAME
+                   && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+    match valid {
+        true => Ok(name.to_lowercase()),
+        false => Err(ArgError::invalid("name", name, Expected::Name)),
+    }
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// File name of an intro without the extension
+pub fn introFile(kind: SoundKind, user_id: u64, name: &str) -> String {
+    match (kind, name) {
+        (SoundKind::Intro, DEFAULT_NAME) => user_id.to_string(),
+        (SoundKind::Intro, _) => format!("{}_{}", user_id, name),
+        (SoundKind::Outro, _) => format!("{}.outro.{}", user_id, name),
+        (SoundKind::Move, _) => format!("{}.move.{}", user_id, name),
+    }
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Reads which kind of sound a command is about, intros if none was given
+pub fn soundKind(args: &ParsedArgs) -> SoundKind {
+    args.text("kind")
+        .and_then(|k| SoundKind::from_str(k).ok())
+        .unwrap_or_default()
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// File name of a version of an intro, the first one keeps the name from before there were versions
+pub fn versionFile(file: &str, version: u32) -> String {
+    match version {
+        0 => file.to_string(),
+        _ => format!("{}.v{}", file, version),
+    }
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Deletes the file of an intro and the clip it was rendered from, if they are there
+pub fn deleteSoundFiles(file: &str) {
+
+    for path in [removeUserAt(file.to_string()), removeUserAt(sourceFile(file))] {
+
+        if !checkVidPath(&path) {
+            continue;
+        }
+
+        if let Err(E) = fs::remove_file(&path) {
+            println!("Unable to delete intro file {}: {}", path, E);
+        }
+    }
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// File name of the clip an intro is rendered from, names can't contain a dot so it can't clash with another intro
+pub fn sourceFile(file: &str) -> String {
+    format!("{}.source", file)
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Path of the intro file
+fn buildIntroPath() -> String {
+
+    let mut current: String = env::current_dir().expect("Unable to get current directory!").to_str().unwrap().to_string();
+    let filepath: &str = "\\src\\config\\";
+
+    current.push_str(filepath);
+
+    current
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Writes all intro collections to disk
+pub async fn writeIntros(library: &IntroLibrary) -> Result<(), RinrError> {
+
+    let mut path: String = buildIntroPath();
+
+    if !Path::new(&path).exists() {
+        create_dir_all(&path).await?;
+        println!("Created Config Directory!");
+    }
+
+    path.push_str("intros.json");
+
+    let json: String = serde_json::to_string(library)
+                                  .map_err(|e| RinrError::Storage(format!("Unable to serialize intros: {}", e)))?;
+
+    let mut file: aFile = aFile::create(&path).await?;
+
+    file.write_all(json.as_bytes()).await?;
+    file.flush().await?;
+
+    println!("Wrote Intros!");
+
+    Ok(())
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Reads all intro collections, empty if there is no file yet
+pub fn readIntros() -> IntroLibrary {
+
+    let mut path: String = buildIntroPath();
+    path.push_str("intros.json");
+
+    let mut file: File = match File::open(&path) {
+        Ok(F) => F,
+        Err(_) => return IntroLibrary::default(),
+    };
+
+    let mut json: String = String::new();
+
+    if let Err(E) = file.read_to_string(&mut json) {
+        println!("Unable to read intros: {}", E);
+        return IntroLibrary::default();
+    }
+
+    match serde_json::from_str(&json) {
+        Ok(L) => L,
+        Err(E) => {
+            println!("Unable to parse intros: {}", E);
+            IntroLibrary::default()
+        },
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use crate::gateway::{MemberInfo, MemoryGateway, Outgoing, MAX_MESSAGE};
+
+    fn sound(name: &str, version: u32) -> IntroSound {
+        IntroSound { name: name.to_string(), file: versionFile(name, version), weight: 1, effects: String::new(), info: None, version, history: vec![] }
+    }
+
+    #[test]
+    fn supersedeKeepsUserSettings() {
+
+        let mut intros: UserIntros = UserIntros::default();
+        intros.add(IntroSound { weight: 7, effects: "bass".to_string(), ..sound("hi", 0) });
+
+        // A new version from $setvideo comes in with the default weight
+        let dropped: Vec<IntroVersion> = intros.add(IntroSound { effects: "echo".to_string(), ..sound("hi", 1) });
+        let current: &IntroSound = intros.find("hi").unwrap();
+
+        assert!(dropped.is_empty());
+        assert_eq!((current.weight, current.file.as_str(), current.effects.as_str(), current.version), (7, "hi.v1", "echo", 1));
+        assert_eq!(current.history.iter().map(|v| (v.version, v.effects.as_str())).collect::<Vec<(u32, &str)>>(), vec![(0, "bass")]);
+    }
+
+    #[test]
+    fn supersedeDropsTheOldestVersions() {
+
+        let mut current: IntroSound = sound("hi", 0);
+        let mut dropped: Vec<IntroVersion> = vec![];
+
+        for v in 1..=(MAX_VERSIONS as u32 + 2) {
+            dropped.extend(current.supersede(sound("hi", v).snapshot()));
+        }
+
+        assert_eq!(current.version, MAX_VERSIONS as u32 + 2);
+        assert_eq!(current.history.len(), MAX_VERSIONS);
+        assert_eq!(dropped.iter().map(|v| v.version).collect::<Vec<u32>>(), vec![0, 1]);
+    }
+
+    #[test]
+    fn rollbackSwapsWithTheHistory() {
+
+        let mut current: IntroSound = IntroSound { weight: 3, ..sound("hi", 0) };
+        current.supersede(sound("hi", 1).snapshot());
+        current.supersede(sound("hi", 2).snapshot());
+
+        assert!(current.rollback(0).is_none());
+        assert!(current.rollback(3).is_none());
+
+        // Two back is version 0, the one that was active goes to the front of the history
+        assert_eq!(current.rollback(2).map(|v| v.version), Some(0));
+        assert_eq!((current.version, current.file.as_str(), current.weight), (0, "hi", 3));
+        assert_eq!(current.history.iter().map(|v| v.version).collect::<Vec<u32>>(), vec![2, 1]);
+        assert_eq!(current.nextVersion(), 3);
+    }
+
+    fn named(intros: &mut UserIntros, times: usize) -> Vec<String> {
+        (0..times).map(|_| intros.pick().unwrap().name.clone()).collect()
+    }
+
+    #[test]
+    fn pickFollowsTheStrategy() {
+
+        let mut intros: UserIntros = UserIntros::default();
+        assert!(intros.pick().is_none());
+
+        for name in ["a", "b", "c"] {
+            intros.add(sound(name, 0));
+        }
+
+        // Fixed plays the newest until another one is selected
+        assert_eq!(named(&mut intros, 3), vec!["c", "c", "c"]);
+        intros.selected = Some("a".to_string());
+        assert_eq!(named(&mut intros, 3), vec!["a", "a", "a"]);
+
+        intros.strategy = Strategy::RoundRobin;
+        assert_eq!(named(&mut intros, 7), vec!["a", "b", "c", "a", "b", "c", "a"]);
+
+        // Keeps its place when an intro is removed under it
+        intros.remove("c");
+        assert_eq!(named(&mut intros, 3), vec!["b", "a", "b"]);
+
+        intros.strategy = Strategy::Weighted;
+        intros.sounds[0].weight = 0;
+        assert!(named(&mut intros, 200).iter().all(|n| n == "b"));
+
+        intros.strategy = Strategy::Random;
+        assert!(named(&mut intros, 50).iter().all(|n| n == "a" || n == "b"));
+
+        for strategy in [Strategy::Fixed, Strategy::Random, Strategy::RoundRobin, Strategy::Weighted] {
+            let mut empty: UserIntros = UserIntros { strategy, ..UserIntros::default() };
+            assert!(empty.pick().is_none());
+        }
+    }
+
+    #[tokio::test]
+    async fn guildListStaysUnderTheMessageLimit() {
+
+        let gateway: Arc<MemoryGateway> = Arc::new(MemoryGateway::default());
+        let mut library: IntroLibrary = IntroLibrary::default();
+
+        for user in 1..=80u64 {
+            let name: String = format!("{:a<30}", user);
+            gateway.members.lock().unwrap().insert((GuildId::new(1), UserId::new(user)), MemberInfo { name, ..MemberInfo::default() });
+
+            for n in ["first", "second", "third"] {
+                library.entry(SoundKind::Intro, user).add(sound(n, 0));
+            }
+        }
+
+        // Someone from another server isn't listed
+        library.entry(SoundKind::Intro, 999).add(sound("first", 0));
+
+        let mut data: TypeMap = TypeMap::new();
+        data.insert::<IntroLibrary>(library);
+
+        listGuild(&gateway.invocation(Arc::new(RwLock::new(data)), 1, 1, "$list")).await.unwrap();
+
+        let messages: Vec<String> = gateway.take().into_iter().map(|o| match o {
+            Outgoing::Message(_, M) => M,
+            other => panic!("Expected only messages, got {:?}", other),
+        }).collect();
+
+        assert!(messages.len() > 1);
+        assert!(messages.iter().all(|m| m.chars().count() <= MAX_MESSAGE));
+        assert!(messages[0].starts_with(&tr(Lang::En, "intro.list_guild_title", &[])));
+        assert_eq!(messages.iter().map(|m| m.lines().filter(|l| l.contains("`first`")).count()).sum::<usize>(), 80);
+    }
+}
@@ -0,0 +1,299 @@
+/*
+    Background jobs of $setvideo
+
+    downloading and rendering an intro takes a while, so $setvideo only starts a job and answers with a status message
+    that is edited while the job goes on and replaced with the result once it is done
+    every user can have one job at a time, a second one would write the same files, and jobs share the slots of the
+    expensive commands so only a few of them run at once, the others wait in the queue until a slot is free
+*/
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serenity::all::{ChannelId, MessageId, UserId};
+use serenity::prelude::TypeMapKey;
+use tokio::sync::watch;
+use tokio::sync::OwnedSemaphorePermit;
+
+use crate::cooldown::waitForSlot;
+use crate::download::CancelToken;
+use crate::error::RinrError;
+use crate::gateway::{Gateway, Invocation};
+use crate::lang::{tr, Lang};
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Const Declaration
+
+// Discord rate limits edits, whatever happens in between is shown by the next one
+const EDIT_INTERVAL: Duration = Duration::from_secs(2);
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Enum Declaration
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobStatus {
+    Queued,                     // Waiting for a free slot
+    Downloading(Option<u8>),    // Percent, if yt-dlp reports it
+    Trimming,
+    Rendering,
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Struct Declaration
+
+// Running and queued jobs by the user that started them
+pub struct IntroJobs;
+
+impl TypeMapKey for IntroJobs {
+    type Value = HashMap<UserId, Job>;
+}
+
+#[derive(Debug)]
+pub struct Job {
+    name: String,
+    status: watch::Receiver<JobStatus>,
+    cancel: CancelToken,
+}
+
+// Handed to the work of a job so it can report how far it is and see if it got cancelled
+pub struct JobHandle {
+    status: watch::Sender<JobStatus>,
+    pub cancel: CancelToken,
+}
+
+
+impl JobStatus {
+    pub fn describe(&self, lang: Lang, name: &str) -> String {
+        match self {
+            JobStatus::Queued => tr(lang, "job.queued", &[("name", name)]),
+            JobStatus::Downloading(None) => tr(lang, "job.downloading", &[("name", name)]),
+            JobStatus::Downloading(Some(P)) => tr(lang, "job.downloading_percent", &[("name", name), ("percent", &P.to_string())]),
+            JobStatus::Trimming => tr(lang, "job.trimming", &[("name", name)]),
+            JobStatus::Rendering => tr(lang, "job.rendering", &[("name", name)]),
+        }
+    }
+}
+
+impl JobHandle {
+    pub fn set(&self, status: JobStatus) {
+        self.status.send_replace(status);
+    }
+
+    // A handle outside of any job, for the tests
+    #[cfg(test)]
+    pub fn detached() -> JobHandle {
+        JobHandle { status: watch::channel(JobStatus::Queued).0, cancel: CancelToken::default() }
+    }
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Starts a job for the author in the background, work returns the message the status is replaced with at the end
+pub async fn startJob<F, Fut>(inv: &Invocation, name: String, work: F) -> Result<(), RinrError>
+where
+    F: FnOnce(Invocation, Arc<JobHandle>) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<String, RinrError>> + Send + 'static,
+{
+    let (send, recv): (watch::Sender<JobStatus>, watch::Receiver<JobStatus>) = watch::channel(JobStatus::Queued);
+    let handle: Arc<JobHandle> = Arc::new(JobHandle { status: send, cancel: CancelToken::default() });
+
+    let running: Option<String> = {
+        let mut u_data = inv.data.write().await;
+        let jobs: &mut HashMap<UserId, Job> = u_data.entry::<IntroJobs>().or_insert_with(HashMap::new);
+
+        match jobs.get(&inv.author) {
+            Some(J) => Some(J.name.clone()),
+            None => {
+                jobs.insert(inv.author, Job { name: name.clone(), status: recv.clone(), cancel: handle.cancel.clone() });
+                None
+            },
+        }
+    };
+
+    if let Some(R) = running {
+        return Err(RinrError::Busy(R));
+    }
+
+    let lang: Lang = inv.lang().await;
+
+    let message: Option<MessageId> = match inv.gateway.send(inv.channel_id, JobStatus::Queued.describe(lang, &name)).await {
+        Ok(M) => Some(M),
+        Err(E) => {
+            println!("Unable to send the status of {}: {}", name, E);
+            None
+        },
+    };
+
+    let inv: Invocation = inv.clone();
+
+    tokio::spawn(async move {
+
+        let reporter = tokio::spawn(reportStatus(inv.gateway.clone(), inv.channel_id, message, recv, lang, name.clone()));
+
+        // The work runs in its own task, if it panics the job still gets cleaned up and answered
+        let job: Invocation = inv.clone();
+        let res: Result<String, RinrError> = match tokio::spawn(async move { runJob(&job, handle, work).await }).await {
+            Ok(R) => R,
+            Err(E) => Err(RinrError::Download(format!("job panicked: {}", E))),
+        };
+
+        reporter.abort();
+
+        if let Some(jobs) = inv.data.write().await.get_mut::<IntroJobs>() {
+            jobs.remove(&inv.author);
+        }
+
+        let out: String = match res {
+            Ok(O) => O,
+            Err(E) => {
+                println!("Job {} of {} failed: {}", name, inv.author_name, E);
+                E.render(lang)
+            },
+        };
+
+        // The status message becomes the result, a new one is only sent if there is none
+        let edited: bool = match message {
+            Some(M) => inv.gateway.edit(inv.channel_id, M, out.clone()).await.is_ok(),
+            None => false,
+        };
+
+        if !edited {
+            inv.say(out).await;
+        }
+    });
+
+    Ok(())
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Waits for a slot and runs the work, cancelling works while waiting as well
+async fn runJob<F, Fut>(inv: &Invocation, handle: Arc<JobHandle>, work: F) -> Result<String, RinrError>
+where
+    F: FnOnce(Invocation, Arc<JobHandle>) -> Fut,
+    Fut: Future<Output = Result<String, RinrError>>,
+{
+    let _permit: Option<OwnedSemaphorePermit> = tokio::select! {
+        biased;
+        _ = handle.cancel.cancelled() => return Err(RinrError::Cancelled("queue".to_string())),
+        permit = waitForSlot(&inv.data) => permit,
+    };
+
+    work(inv.clone(), handle).await
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Edits the status message whenever the status of a job changes
+async fn reportStatus(gateway: Arc<dyn Gateway>, channel: ChannelId, message: Option<MessageId>, mut status: watch::Receiver<JobStatus>, lang: Lang, name: String) {
+
+    let message: MessageId = match message {
+        Some(M) => M,
+        None => return,
+    };
+
+    while status.changed().await.is_ok() {
+
+        let current: JobStatus = *status.borrow_and_update();
+
+        if let Err(E) = gateway.edit(channel, message, current.describe(lang, &name)).await {
+            println!("Unable to edit the status of {}: {}", name, E);
+        }
+
+        tokio::time::sleep(EDIT_INTERVAL).await;
+    }
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Checks if the author has a job already, fails with its name if so
+pub async fn checkRunning(inv: &Invocation) -> Result<(), RinrError> {
+
+    let running: Option<String> = match inv.data.read().await.get::<IntroJobs>() {
+        Some(J) => J.get(&inv.author).map(|j| j.name.clone()),
+        None => None,
+    };
+
+    match running {
+        Some(R) => Err(RinrError::Busy(R)),
+        None => Ok(()),
+    }
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Tells the author how far their job is
+pub async fn jobStatus(inv: &Invocation) -> Result<(), RinrError> {
+
+    let job: Option<(String, JobStatus)> = match inv.data.read().await.get::<IntroJobs>() {
+        Some(J) => J.get(&inv.author).map(|j| (j.name.clone(), *j.status.borrow())),
+        None => None,
+    };
+
+    match job {
+        Some((N, S)) => inv.say(S.describe(inv.lang().await, &N)).await,
+        None => inv.reply("job.none", &[]).await,
+    }
+
+    Ok(())
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Cancels the job of the author, the job itself reports that it stopped
+pub async fn cancelJob(inv: &Invocation) -> Result<(), RinrError> {
+
+    let name: Option<String> = match inv.data.read().await.get::<IntroJobs>() {
+        Some(J) => J.get(&inv.author).map(|j| {
+            j.cancel.cancel();
+            j.name.clone()
+        }),
+        None => None,
+    };
+
+    match name {
+        Some(N) => inv.reply("job.cancelled", &[("name", &N)]).await,
+        None => inv.reply("job.none", &[]).await,
+    }
+
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serenity::prelude::{RwLock, TypeMap};
+    use crate::gateway::{MemoryGateway, Outgoing};
+
+    async fn explode(_inv: Invocation, _handle: Arc<JobHandle>) -> Result<String, RinrError> {
+        panic!("the work blew up")
+    }
+
+    #[tokio::test]
+    async fn panickingJobIsCleanedUp() {
+
+        let gateway: Arc<MemoryGateway> = Arc::new(MemoryGateway::default());
+        let data: Arc<RwLock<TypeMap>> = Arc::new(RwLock::new(TypeMap::new()));
+        let inv: Invocation = gateway.invocation(data.clone(), 1, 10, "$setvideo");
+
+        startJob(&inv, "intro".to_string(), explode).await.unwrap();
+        assert!(data.read().await.get::<IntroJobs>().unwrap().contains_key(&inv.author));
+
+        while data.read().await.get::<IntroJobs>().unwrap().contains_key(&inv.author) {
+            tokio::task::yield_now().await;
+        }
+
+        // Give the job the chance to answer after it let go of the entry
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+
+        let error: String = RinrError::Download(String::new()).render(Lang::default());
+        assert!(gateway.take().iter().any(|o| matches!(o, Outgoing::Edit(_, _, C) if *C == error)));
+    }
+}
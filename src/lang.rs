@@ -0,0 +1,342 @@
+/*
+    Message catalog for everything the bot says
+
+    every entry has a key and one text per language, placeholders are written as {name} and filled in by tr
+    the language is picked per guild in the config and falls back to english
+*/
+use serde::{Deserialize, Serialize};
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Enum Declaration
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Lang {
+    #[default]
+    En,
+    De,
+}
+
+impl Lang {
+    pub fn from_code(code: &str) -> Option<Lang> {
+        match code.to_lowercase().as_str() {
+            "en" | "english" => Some(Lang::En),
+            "de" | "german" | "deutsch" => Some(Lang::De),
+            _ => None,
+        }
+    }
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Const Declaration
+
+//  key                                 english                                                                         german
+const CATALOG: &[(&str, &str, &str)] = &[
+    // General
+    ("general.invalid_argument",        "Invalid argument {error}\nUsage: `{usage}`",                                   "Ungültiges Argument {error}\nBenutzung: `{usage}`"),
+    ("general.cooldown",                "`{command}` is busy or on cooldown, try again in {secs}s",                     "`{command}` ist gerade beschäftigt, versuch es in {secs}s nochmal"),
+    ("general.denied",                  "You are not allowed to use `${command}`",                                      "Du darfst `${command}` nicht benutzen"),
+    ("general.denied_permission",       ", it needs the {permissions} permission",                                      ", dafür brauchst du die Berechtigung {permissions}"),
+    ("general.denied_roles",            " or one of these roles: {roles}",                                             " oder eine dieser Rollen: {roles}"),
+    ("general.language_set",            "Language set to English",                                                      "Sprache auf Deutsch gestellt"),
+    ("general.deleted_message",         "I saw that",                                                                   "Ich sehe das"),
+
+    // Help
+    ("help.title",                      "**Commands**",                                                                 "**Befehle**"),
+    ("help.not_implemented",            " (Not Implemented Yet)",                                                       " (Noch nicht implementiert)"),
+    ("help.footer",                     "Use `{prefix}help <command>` for details.",                                    "Benutz `{prefix}help <befehl>` für Details."),
+    ("help.unknown",                    "Unknown command `{command}`",                                                  "Unbekannter Befehl `{command}`"),
+    ("help.aliases",                    "Aliases: {aliases}",                                                           "Aliase: {aliases}"),
+    ("help.default",                    "default {value}",                                                              "Standard {value}"),
+
+    // Arguments
+    ("arg.unknown_option",              "`{key}`: unknown option",                                                      "`{key}`: unbekannte Option"),
+    ("arg.unknown_flag",                "`{key}`: unknown flag",                                                        "`{key}`: unbekannter Schalter"),
+    ("arg.unexpected_mention",          "`{key}`: unexpected mention",                                                  "`{key}`: unerwartete Erwähnung"),
+    ("arg.unexpected_argument",         "`{key}`: unexpected argument",                                                 "`{key}`: unerwartetes Argument"),
+    ("arg.required",                    "`{key}`: is required",                                                         "`{key}`: fehlt"),
+    ("arg.required_for",                "`{key}`: is required for {context}",                                           "`{key}`: wird für {context} gebraucht"),
+    ("arg.duplicate",                   "`{key}`: was given more than once",                                            "`{key}`: wurde mehrfach angegeben"),
+    ("arg.range",                       "`{key}`: has to be between {min} and {max}",                                   "`{key}`: muss zwischen {min} und {max} liegen"),
+    ("arg.conflict",                    "`{key}`: can't be used together with `{other}`",                                "`{key}`: geht nicht zusammen mit `{other}`"),
+    ("arg.unclosed",                    "`{key}`: is missing a closing {close}",                                        "`{key}`: es fehlt ein schließendes {close}"),
+    ("arg.unquotable",                  "`{key}`: can't hold a \" together with an unmatched [ or ]",                   "`{key}`: kann kein \" zusammen mit einer offenen [ oder ] enthalten"),
+    ("arg.invalid",                     "`{key}`: `{value}` is not {expected}",                                         "`{key}`: `{value}` ist nicht {expected}"),
+    ("arg.expected_number",             "a whole number",                                                               "eine ganze Zahl"),
+    ("arg.expected_decimal",            "a number like 1.5",                                                            "eine Zahl wie 1.5"),
+    ("arg.expected_bool",               "1 or 0",                                                                       "1 oder 0"),
+    ("arg.expected_user",               "a user",                                                                       "ein Nutzer"),
+    ("arg.expected_timestamp",          "a time like 90, 1:23.5 or 1m30.25s",                                           "eine Zeit wie 90, 1:23.5 oder 1m30.25s"),
+    ("arg.expected_between",            "a number from {min} to {max}",                                                 "eine Zahl von {min} bis {max}"),
+    ("arg.expected_after",              "a time after {time}",                                                          "eine Zeit nach {time}"),
+    ("arg.expected_date",               "a date like DD/MM/YYYY",                                                       "ein Datum wie TT/MM/JJJJ"),
+    ("arg.expected_time",               "a time like HH:MM",                                                            "eine Uhrzeit wie HH:MM"),
+    ("arg.expected_id",                 "a valid ID",                                                                   "eine gültige ID"),
+    ("arg.expected_name",               "a name of up to {max} letters, numbers, - or _",                               "ein Name aus bis zu {max} Buchstaben, Zahlen, - oder _"),
+    ("arg.expected_one_of",             "one of {choices}",                                                             "eins von {choices}"),
+
+    // Errors
+    ("error.invalid_link",              "No valid link given!",                                                         "Kein gültiger Link!"),
+    ("error.invalid_upload",            "Attach an mp3, ogg, wav, m4a or webm file of up to {mb}MB and {minutes} minutes!", "Häng eine mp3, ogg, wav, m4a oder webm Datei mit bis zu {mb}MB und {minutes} Minuten an!"),
+    ("error.download",                  "Unable to download, try again later!",                                         "Download fehlgeschlagen, versuch es später nochmal!"),
+    ("error.cancelled",                 "The download was cancelled",                                                   "Der Download wurde abgebrochen"),
+    ("error.voice",                     "Unable to play in the voice channel!",                                         "Im Sprachkanal kann gerade nichts abgespielt werden!"),
+    ("error.storage",                   "Unable to save that, try again later!",                                        "Das konnte nicht gespeichert werden, versuch es später nochmal!"),
+    ("error.discord",                   "Discord didn't accept that, try again later!",                                 "Discord hat das nicht angenommen, versuch es später nochmal!"),
+
+    // Intros
+    ("job.queued",                      "`{name}` is waiting for its turn",                                             "`{name}` wartet, bis es dran ist"),
+    ("job.downloading",                 "Downloading `{name}`...",                                                      "Lade `{name}` herunter..."),
+    ("job.downloading_percent",         "Downloading `{name}`... {percent}%",                                           "Lade `{name}` herunter... {percent}%"),
+    ("job.trimming",                    "Trimming `{name}`...",                                                         "Schneide `{name}`..."),
+    ("job.rendering",                   "Rendering `{name}`...",                                                        "Erstelle `{name}`..."),
+    ("job.running",                     "`{name}` is still in the works, `$setvideo status` shows how far it is and `$setvideo cancel` stops it", "`{name}` ist noch in Arbeit, `$setvideo status` zeigt wie weit es ist und `$setvideo cancel` bricht es ab"),
+    ("job.none",                        "You have nothing in the works",                                                "Bei dir ist gerade nichts in Arbeit"),
+    ("job.cancelled",                   "Cancelling `{name}`",                                                          "Breche `{name}` ab"),
+    ("intro.rendered",                  "Intro `{name}` rendered again with the new effects",                           "Intro `{name}` mit den neuen Effekten neu erstellt"),
+    ("intro.added",                     "`{name}` saved, {start} to {end}!\nFor User: {user}",                          "`{name}` gespeichert, {start} bis {end}!\nFür: {user}"),
+    ("intro.too_many",                  "You already have {max} intros, remove one first",                              "Du hast schon {max} Intros, lösch zuerst eins"),
+    ("intro.empty",                     "You don't have any intros yet, add one with $setvideo",                        "Du hast noch keine Intros, füg eins mit $setvideo hinzu"),
+    ("intro.list_title",                "**{kind} of {user}** (selection: {strategy})",                                 "**{kind} von {user}** (Auswahl: {strategy})"),
+    ("intro.list_weight",               "weight {weight}",                                                              "Gewicht {weight}"),
+    ("intro.list_selected",             "selected",                                                                     "ausgewählt"),
+    ("intro.unknown",                   "You don't have an intro called `{name}`",                                      "Du hast kein Intro namens `{name}`"),
+    ("intro.unknown_other",             "{user} doesn't have an intro called `{name}`",                                  "{user} hat kein Intro namens `{name}`"),
+    ("intro.empty_other",               "{user} doesn't have any intros",                                               "{user} hat keine Intros"),
+    ("intro.not_in_voice",              "Join a voice channel first",                                                   "Geh zuerst in einen Sprachkanal"),
+    ("intro.file",                      "Intro `{name}` of {user}",                                                     "Intro `{name}` von {user}"),
+    ("intro.info",                      "**`{name}` of {user}**\n{title} ({source})\n{start} to {end} of {duration}, effects: {effects}\nSet by {setter} on {date}",
+                                        "**`{name}` von {user}**\n{title} ({source})\n{start} bis {end} von {duration}, Effekte: {effects}\nGesetzt von {setter} am {date}"),
+    ("intro.info_missing",              "`{name}` is from before RinR kept where intros come from, effects: {effects}",  "`{name}` ist von bevor RinR sich gemerkt hat woher Intros kommen, Effekte: {effects}"),
+    ("intro.info_no_effects",           "none",                                                                         "keine"),
+    ("intro.history_title",             "**Older versions of `{name}`**, `$intro rollback <n>` brings one back",        "**Ältere Versionen von `{name}`**, `$intro rollback <n>` holt eine zurück"),
+    ("intro.history_current",           "Now: {entry}",                                                                 "Jetzt: {entry}"),
+    ("intro.history_entry",             "{title}, {start} to {end}, effects: {effects}, {date}",                        "{title}, {start} bis {end}, Effekte: {effects}, {date}"),
+    ("intro.history_entry_old",         "from before RinR kept where intros come from, effects: {effects}",             "von bevor RinR sich gemerkt hat woher Intros kommen, Effekte: {effects}"),
+    ("intro.history_empty",             "`{name}` has no older versions yet",                                           "`{name}` hat noch keine älteren Versionen"),
+    ("intro.unknown_version",           "`{name}` has no older version {version}, see `$intro history`",                "`{name}` hat keine ältere Version {version}, siehe `$intro history`"),
+    ("intro.rolled_back",               "`{name}` is back to {entry}",                                                  "`{name}` ist wieder {entry}"),
+    ("intro.list_guild_title",          "**Sounds on this server**",                                                    "**Sounds auf diesem Server**"),
+    ("intro.list_guild_empty",          "Nobody here has an intro yet, add one with $setvideo",                         "Hier hat noch niemand ein Intro, füg eins mit $setvideo hinzu"),
+    ("intro.removed",                   "Removed intro `{name}`",                                                       "Intro `{name}` gelöscht"),
+    ("intro.selected",                  "`{name}` will be played from now on",                                          "Ab jetzt wird `{name}` abgespielt"),
+    ("intro.strategy_set",              "Intros will be picked {strategy} from now on",                                 "Intros werden ab jetzt {strategy} ausgewählt"),
+    ("intro.weight_set",                "`{name}` now has weight {weight}",                                             "`{name}` hat jetzt Gewicht {weight}"),
+    ("intro.strategy_fixed",            "fixed",                                                                        "fest"),
+    ("intro.strategy_random",           "randomly",                                                                     "zufällig"),
+    ("intro.strategy_roundrobin",       "in rotation",                                                                  "der Reihe nach"),
+    ("intro.strategy_weighted",         "randomly by weight",                                                           "zufällig nach Gewicht"),
+
+    // Voice
+    ("voice.not_playing",               "Nothing is playing right now",                                                 "Gerade läuft nichts"),
+    ("voice.disconnected",              "Left the voice channel",                                                       "Sprachkanal verlassen"),
+    ("voice.not_connected",             "I'm not in a voice channel",                                                   "Ich bin in keinem Sprachkanal"),
+    ("voice.queue_full",                "Too much is waiting to be played already, try again in a bit",                  "Es wartet schon zu viel, versuch's gleich nochmal"),
+    ("sounds.settings",                 "Intros: {intros}\nOutros: {outros}\nMove sounds: {moves}\nOutro delay: {delay}\nOutros into empty channels: {empty}", "Intros: {intros}\nOutros: {outros}\nWechselsounds: {moves}\nOutro Verzögerung: {delay}\nOutros in leere Kanäle: {empty}"),
+    ("sounds.on",                       "on",                                                                           "an"),
+    ("sounds.off",                      "off",                                                                          "aus"),
+    ("sound.intro",                     "Intros",                                                                       "Intros"),
+    ("sound.outro",                     "Outros",                                                                       "Outros"),
+    ("sound.move",                      "Move sounds",                                                                  "Wechselsounds"),
+    ("voice.volume",                    "Sounds play at {percent}% here",                                               "Sounds laufen hier mit {percent}%"),
+    ("voice.volume_set",                "Volume set to {percent}%",                                                     "Lautstärke auf {percent}% gestellt"),
+
+    // Timer
+    ("timer.invalid",                   "You didn't enter a valid time amount, dumbass!",                               "Du hast keine gültige Zeit angegeben, du Depp!"),
+    ("timer.set",                       "Timer set for {minutes} minute(s) and {seconds} second(s)",                    "Timer auf {minutes} Minute(n) und {seconds} Sekunde(n) gestellt"),
+    ("timer.ended",                     "Your timer has ended!\n<@{user}>",                                             "Dein Timer ist abgelaufen!\n<@{user}>"),
+
+    // Predictions
+    ("predict.empty",                   "No Predictions at the moment!",                                                "Gerade gibt es keine Vorhersagen!"),
+    ("predict.title",                   "Current Predictions: ",                                                        "Aktuelle Vorhersagen: "),
+    ("predict.entry",                   "{prediction} by {user}, ID:{id}",                                              "{prediction} von {user}, ID:{id}"),
+    ("predict.added",                   "Added Prediction",                                                             "Vorhersage hinzugefügt"),
+
+    // Events
+    ("event.subscribed",                "Successfully subscribed to Event",                                             "Event erfolgreich abonniert"),
+    ("event.unsubscribed",              "Successfully unsubscribed from Event",                                         "Event erfolgreich deabonniert"),
+    ("event.deleted",                   "Successfully deleted event",                                                   "Event erfolgreich gelöscht"),
+    ("event.created",                   "Successfully added event",                                                     "Event erfolgreich hinzugefügt"),
+    ("event.no_channel",                "Please configure the Bot channel!\nYou can do this by using $event channel.",  "Bitte richte zuerst den Bot Kanal ein!\nDas geht mit $event channel."),
+    ("event.channel_set",               "Bot Channel Configured!",                                                      "Bot Kanal eingerichtet!"),
+    ("event.none",                      "No Events Available",                                                          "Keine Events vorhanden"),
+    ("event.format",                    "Event: {name}\nID: {id}\nMessage: {message}\nTime: {time}\nSubscribers: {subscribers}\nCommand: {command}\nDate: {date}\nInterval: {interval}\n\n",
+                                        "Event: {name}\nID: {id}\nNachricht: {message}\nUhrzeit: {time}\nAbonnenten: {subscribers}\nBefehl: {command}\nDatum: {date}\nIntervall: {interval}\n\n"),
+    ("event.no_subscribers",            "No Subscribers",                                                               "Keine Abonnenten"),
+    ("event.no_message",                "No Message",                                                                   "Keine Nachricht"),
+    ("event.no_command",                "No Command",                                                                   "Kein Befehl"),
+
+    // Secret Santa
+    ("santa.assigned",                  "Your Secret Santa is <@{user}>",                                               "Dein Wichtelpartner ist <@{user}>"),
+
+    // Fortnite
+    ("fortnite.item",                   "{image}\n\n**Name:** {name}\n**Price:** {price}\n**Time Remaining:** {time}\n", "{image}\n\n**Name:** {name}\n**Preis:** {price}\n**Verbleibende Zeit:** {time}\n"),
+    ("fortnite.no_name",                "No Name",                                                                      "Kein Name"),
+    ("fortnite.no_price",               "No Price",                                                                     "Kein Preis"),
+    ("fortnite.no_time",                "Unknown Time Remaining",                                                       "Unbekannte Restzeit"),
+];
+
+// German texts for the usage and argument descriptions of the registry, keyed by command and command.argument
+// English is what the registry holds, so it isn't repeated here
+//  key                                 german
+const HELP: &[(&str, &str)] = &[
+    ("help",                            "Zeigt alle Befehle oder wie ein Befehl benutzt wird"),
+    ("help.command",                    "Befehl, der gezeigt werden soll"),
+
+    ("test",                            "Testbefehl"),
+
+    ("setvideo",                        "Fügt ein Intro per Link oder Audiodatei hinzu, ohne beides wird es mit neuen Effekten neu erstellt"),
+    ("setvideo.url",                    "Ein Link, den yt-dlp kennt, oder häng eine Audiodatei an"),
+    ("setvideo.start",                  "Startpunkt, wie 90 oder 1:23.5, sonst das t= aus dem Link"),
+    ("setvideo.length",                 "Länge des Clips in Sekunden, wie 3.25"),
+    ("setvideo.end",                    "Endpunkt, wie 1:30, statt einer Länge"),
+    ("setvideo.name",                   "Name des Intros, ersetzt das mit dem gleichen Namen"),
+    ("setvideo.kind",                   "Wann der Sound spielt"),
+    ("setvideo.fadein",                 "Länge des Einblendens, wie 0.5"),
+    ("setvideo.fadeout",                "Länge des Ausblendens, wie 1.5"),
+    ("setvideo.speed",                  "Geschwindigkeit von 0.5 bis 2"),
+    ("setvideo.pitch",                  "Tonhöhe in Halbtönen von -12 bis 12"),
+    ("setvideo.bass",                   "Bassverstärkung in dB von -20 bis 20"),
+    ("setvideo.reverse",                "Spielt den Clip rückwärts"),
+    ("setvideo.trimsilence",            "Schneidet Stille am Anfang und am Ende ab"),
+    ("setvideo.status",                 "Zeigt, wie weit dein Intro ist"),
+    ("setvideo.cancel",                 "Bricht dein Intro ab, das gerade in Arbeit ist"),
+
+    ("intro",                           "Listet, löscht, spielt und wählt zwischen deinen Intros, Outros und Move-Sounds"),
+    ("intro.mode",                      "Was passieren soll, info zeigt woher ein Intro kommt, rollback holt eine ältere Version zurück"),
+    ("intro.name",                      "Name des Intros, oder fixed|random|roundrobin|weighted (strategy)"),
+    ("intro.version",                   "Wie viele Versionen zurück, siehe history (rollback)"),
+    ("intro.weight",                    "Wie oft das Intro drankommt (weight)"),
+    ("intro.user",                      "Wessen Intro (file, info, list)"),
+    ("intro.kind",                      "Welche Sounds"),
+
+    ("list",                            "Listet alle mit einem Intro auf diesem Server"),
+
+    ("disconnect",                      "Trennt den Bot vom Sprachkanal"),
+
+    ("stfu",                            "Stoppt den aktuellen Sound"),
+
+    ("volume",                          "Zeigt oder setzt die Lautstärke der Sounds auf diesem Server"),
+    ("volume.percent",                  "Lautstärke in Prozent, ohne wird die aktuelle gezeigt"),
+
+    ("sounds",                          "Zeigt oder setzt, welche Sounds auf diesem Server spielen und wie sich Outros verhalten"),
+    ("sounds.kind",                     "Welche Sounds an- oder ausgeschaltet werden"),
+    ("sounds.enabled",                  "on oder off"),
+    ("sounds.delay",                    "Wie lange vor einem Outro gewartet wird, wie 2.5"),
+    ("sounds.empty",                    "Outros spielen, wenn niemand mehr im Kanal ist"),
+
+    ("kys",                             "kys"),
+
+    ("timer",                           "Startet einen Timer und spielt einen Sound in deinem Sprachkanal, wenn er abläuft"),
+    ("timer.minutes",                   "Minuten"),
+    ("timer.seconds",                   "Sekunden"),
+
+    ("win",                             "W oder L"),
+
+    ("ban",                             "Bannt einen Nutzer"),
+    ("ban.user",                        "Nutzer, der gebannt wird"),
+
+    ("userlist",                        "Listet Nutzer"),
+
+    ("say",                             "Wiederholt deine Nachricht und löscht das Original"),
+    ("say.text",                        "Was gesagt werden soll"),
+
+    ("predict",                         "Listet, fügt hinzu oder löscht Vorhersagen"),
+    ("predict.mode",                    "Was passieren soll"),
+    ("predict.text",                    "Vorhersage zum Hinzufügen oder ID zum Löschen"),
+
+    ("poll",                            "Startet eine Umfrage"),
+    ("poll.question",                   "Frage der Umfrage"),
+
+    ("fn",                              "Zeigt die neuen Items im Fortnite-Shop"),
+
+    ("event",                           "Erstellt, listet, löscht und abonniert Events im Bot-Kanal"),
+    ("event.mode",                      "Was passieren soll"),
+    ("event.name",                      "Name des Events (create)"),
+    ("event.description",               "Nachricht des Events (create)"),
+    ("event.time",                      "Uhrzeit als HH:MM (create)"),
+    ("event.subscribe",                 "Das neue Event abonnieren (create)"),
+    ("event.command",                   "Befehl, der mit dem Event ausgeführt wird (create)"),
+    ("event.date",                      "Datum als DD/MM/YYYY (create)"),
+    ("event.interval",                  "Wie oft sich das Event wiederholt (create)"),
+    ("event.id",                        "Event-ID (delete, subscribe, unsubscribe)"),
+
+    ("santa",                           "Wichteln, start schickt allen, wen sie beschenken"),
+    ("santa.mode",                      "Was passieren soll"),
+
+    ("language",                        "Stellt die Sprache ein, in der der Bot auf diesem Server antwortet"),
+    ("language.language",               "Sprache, die benutzt werden soll"),
+];
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Looks up a message and fills in its placeholders
+pub fn tr(lang: Lang, key: &str, vars: &[(&str, &str)]) -> String {
+
+    let mut out: String = match CATALOG.iter().find(|(k, _, _)| *k == key) {
+        Some((_, en, de)) => match lang {
+            Lang::En => en.to_string(),
+            Lang::De => de.to_string(),
+        },
+        None => {
+            println!("Missing message in catalog: {}", key);
+            return key.to_string();
+        },
+    };
+
+    for (name, value) in vars {
+        out = out.replace(&format!("{{{}}}", name), value);
+    }
+
+    out
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Gets the description of a command or argument, english comes straight from the registry
+pub fn describe(lang: Lang, key: &str, english: &'static str) -> &'static str {
+
+    if lang == Lang::En {
+        return english;
+    }
+
+    match HELP.iter().find(|(k, _)| *k == key) {
+        Some((_, de)) => de,
+        None => {
+            println!("Missing description in catalog: {}", key);
+            english
+        },
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::REGISTRY;
+    use crate::slash::MAX_DESCRIPTION;
+
+    #[test]
+    fn everyDescriptionIsTranslated() {
+
+        let mut keys: Vec<String> = vec![];
+
+        for c in REGISTRY {
+            keys.push(c.name.to_string());
+            keys.extend(c.args.iter().map(|a| format!("{}.{}", c.name, a.name)));
+        }
+
+        for key in &keys {
+            let de: Option<&str> = HELP.iter().find(|(k, _)| *k == key.as_str()).map(|(_, d)| *d);
+
+            // Discord takes localized descriptions with the same limit
+            assert!(de.is_some_and(|d| d.chars().count() <= MAX_DESCRIPTION), "{}", key);
+        }
+
+        for (key, _) in HELP {
+            assert!(keys.iter().any(|k| k == key), "{} is not in the registry", key);
+        }
+    }
+}
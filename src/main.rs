@@ -4,15 +4,15 @@
 
 use std::collections::HashMap;
 use std::{env, thread};
-use std::sync::mpsc;
+use std::sync::{mpsc, Arc, Mutex};
 use std::sync::mpsc::{Sender, Receiver};
 
 use helper::{EventSignal, RinrOptions, Santa};
-use tokio;
 use dotenvy::dotenv;
 use songbird::SerenityInit;
 use serenity::model::prelude::Member;
 use serenity::model::voice::VoiceState;
+use serenity::model::application::Interaction;
 use serenity::{async_trait,
                  Client, client::*, 
                  prelude::{GatewayIntents, EventHandler},
@@ -22,18 +22,38 @@ use serenity::{async_trait,
 mod helper;
 use crate::helper::{fillStruct, checkDirs, readConfig, DailyEventSignalKey};
 
+mod args;
+
+mod lang;
+
+mod error;
+
+mod gateway;
+use crate::gateway::{Gateway, Invocation, SerenityGateway};
+
 mod command;
 use crate::command::{checkCommand, executeCommand,
-                     User, COMMAND};
+                     User, CommandSpec};
 
 mod voice;
-use crate::voice::joinVoice;
+use crate::voice::{joinVoice, BotId};
+
+mod intro;
+use crate::intro::{readIntros, IntroLibrary};
+
+mod effects;
+
+mod download;
+use crate::download::checkTools;
+
+mod jobs;
+
+mod queue;
 
 mod predict;
 use crate::predict::UserPrediction;
 
 mod timer;
-// TODO: send audio file to discord channel command
 // TODO: Image macro
 // TODO: set bot channel info event system
 // TODO: admin checked commands to wipe the sound folder
@@ -51,6 +71,19 @@ use crate::event::*;
 
 mod santa;
 
+mod permission;
+
+mod cooldown;
+use crate::cooldown::Cooldowns;
+
+mod slash;
+use crate::slash::{registerCommands, slashHandler};
+
+mod clock;
+use crate::clock::{Clock, ClockKey, SystemClock};
+
+mod simulate;
+
 //--------------------------------------------------------------------------------------------------------------------------
 // Struct Declaration
 
@@ -63,21 +96,41 @@ impl EventHandler for Handler {
 
         // Aquire Lock
         let mut u_data = ctx.data.write().await;           
+
+        // Ready comes again after every reconnect, everything below only has to happen once
+        if u_data.contains_key::<DailyEventSignalKey>() {
+            println!("{}, Reconnected to Server!", ready.user.name);
+            return;
+        }
         
         // Read config file
         let config: RinrOptions = readConfig().await;
         println!("Config: {:#?}", config);
 
+        u_data.insert::<Cooldowns>(Arc::new(Mutex::new(Cooldowns::new(config.concurrency))));
+
+        // Shared between the event thread and the command handlers
+        let config: Arc<Mutex<RinrOptions>> = Arc::new(Mutex::new(config));
+        let thread_config: Arc<Mutex<RinrOptions>> = config.clone();
+
         // Create channel for new thread
         let (send, recv): (Sender<EventSignal>, Receiver<EventSignal>) = mpsc::channel();
 
+        // The event thread answers through the same gateway as the handlers
+        let gateway: Arc<dyn Gateway> = Arc::new(SerenityGateway::new(ctx.clone()));
+        let clock: Arc<dyn Clock> = Arc::new(SystemClock);
+        let thread_clock: Arc<dyn Clock> = clock.clone();
+
         let _event_handler = thread::spawn(move || {
-            loops(config, recv);
+            loops(thread_config, recv, gateway, thread_clock);
         });
 
 
 
         u_data.insert::<DailyEventSignalKey>(send);
+        u_data.insert::<RinrOptions>(config);
+        u_data.insert::<ClockKey>(clock);
+        u_data.insert::<BotId>(ready.user.id);
 
 
         // Gets saved Data
@@ -96,6 +149,11 @@ impl EventHandler for Handler {
 
         println!("Loaded Predictions: {:#?}", u_map);
 
+        drop(u_data);
+
+        // Registers the slash versions of all commands
+        registerCommands(&ctx).await;
+
         println!("{}, Connected to Server!", ready.user.name);
     }
 
@@ -103,16 +161,24 @@ impl EventHandler for Handler {
         
         println!("{} said : {:?}", msg.author.name, msg.content);   // Debug: Shows message contents
         
-        //if msg.author.id.0 == BOT_ID {return;}                    // Uncomment if you don't want the bot to execute commands it repeated using say
+        //if Some(msg.author.id) == voice::botId(&ctx.data).await {return;}     // Uncomment if you don't want the bot to execute commands it repeated using say
 
-        let command: COMMAND = checkCommand(&msg).await;            // Checks if a message is a command
-
-        if command == COMMAND::INVALID {return;}                    // Returns early if there is no command
+        let command: &CommandSpec = match checkCommand(&msg.content).await {    // Checks if a message is a command
+            Some(C) => C,
+            None => return,                                                     // Returns early if there is no command
+        };
 
-        executeCommand(command, &msg, &ctx).await;
+        executeCommand(command, &Invocation::fromMessage(&msg, &ctx)).await;
      
     }
 
+    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+
+        if let Interaction::Command(command) = interaction {
+            slashHandler(&ctx, &command).await;
+        }
+    }
+
     #[cfg(feature = "delete_annotation")]
     async fn message_delete(&self, ctx: Context, cid: ChannelId, _dmid: MessageId, gid: Option<GuildId>) {
         
@@ -124,22 +190,30 @@ impl EventHandler for Handler {
         let limit: Option<u8> = 1.into();
 
         let audit = ctx.http.get_audit_logs(gid.unwrap().0, action_type, b_id, before, limit).await;
-        
+
+        let bot_id: Option<serenity::model::id::UserId> = voice::botId(&ctx.data).await;
+
         for a in audit.unwrap().entries {
-            if a.target_id.unwrap() == BOT_ID {
+            if bot_id.is_some_and(|b| a.target_id.unwrap() == b.get()) {
                 return;
             }
         }
 
-        if let Err(why) = cid.say(&ctx.http, "Ich sehe das").await {
+        let lang: lang::Lang = helper::guildLang(&ctx.data, gid).await;
+
+        if let Err(why) = cid.say(&ctx.http, lang::tr(lang, "general.deleted_message", &[])).await {
             println!("Send Message failed. Error: {:?}", why)
         }
     }
 
     async fn voice_state_update(&self, ctx: Context, old: Option<VoiceState>, new: VoiceState) {
-        match joinVoice(ctx, old, &new).await {
-            Ok(()) => println!("{} Successfully joined VC!", new.member.unwrap().user.name),
-            Err(_) => (),
+        let data = ctx.data.clone();
+        let gateway: Arc<dyn Gateway> = Arc::new(SerenityGateway::new(ctx));
+
+        match joinVoice(&gateway, &data, old, &new).await {
+            Ok(true) => println!("Played sound for {} in VC!", new.user_id),
+            Ok(false) => (),
+            Err(E) => println!("Unable to play intro for {}: {}", new.user_id, E),
         };
     }
 
@@ -158,12 +232,23 @@ impl EventHandler for Handler {
 #[tokio::main]
 async fn main() {
 
+    // Runs against the console instead of discord, doesn't need a token
+    if env::args().any(|a| a == "--simulate") {
+        checkDirs().await;
+        checkTools().await;
+        simulate::simulate(env::args().collect()).await;
+        return;
+    }
+
     // Sets DC Token as ENV
     dotenv().expect("Please provide a .env file with your Bot Token!");
 
     // Checks if dirs exist and creates them if not
     checkDirs().await;
 
+    // Only warns, everything but $setvideo works without them
+    checkTools().await;
+
     // Lets bot know which event it should listen to
     let intents = GatewayIntents::GUILD_MESSAGES
     | GatewayIntents::DIRECT_MESSAGES
@@ -190,6 +275,8 @@ async fn main() {
             u_data.insert::<User>(HashMap::default());
             
             u_data.insert::<Santa>(Santa::default());
+
+            u_data.insert::<IntroLibrary>(readIntros());
         }
  
     // Connects to Server
@@ -0,0 +1,161 @@
+use std::sync::{Arc, Mutex};
+
+use serenity::all::{Permissions, RoleId};
+use serenity::prelude::{RwLock, TypeMap};
+
+use crate::args::ParsedArgs;
+use crate::command::CommandSpec;
+use crate::error::RinrError;
+use crate::gateway::{Invocation, MemberInfo};
+use crate::helper::{PermissionRule, RinrOptions};
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Const Declaration
+
+// Used when the config has no rule for a command, everything not listed here is open to everyone
+// `<command> set` only applies to commands without modes when they get arguments, they just show something without them
+const DEFAULT_RULES: &[(&str, &[&str])] = &[
+    ("event delete",    &["MANAGE_GUILD"]),
+    ("event channel",   &["MANAGE_GUILD"]),
+    ("predict remove",  &["MANAGE_MESSAGES"]),
+    ("santa start",     &["MANAGE_GUILD"]),
+    ("language",        &["MANAGE_GUILD"]),
+    ("volume set",      &["MANAGE_GUILD"]),
+    ("sounds set",      &["MANAGE_GUILD"]),
+];
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Checks if the author of a message may run a command, returns what is missing if not
+pub async fn checkPermission(cmd: &CommandSpec, args: &ParsedArgs, inv: &Invocation) -> Result<(), RinrError> {
+
+    // Sub-modes like `event delete` come first so they can be stricter than the command itself
+    let mut keys: Vec<String> = vec![];
+    match args.text("mode") {
+        Some(mode) => keys.push(format!("{} {}", cmd.name, mode)),
+        None if !args.isEmpty() => keys.push(format!("{} set", cmd.name)),
+        None => (),
+    }
+    keys.push(cmd.name.to_string());
+
+    let (key, rule): (String, PermissionRule) = match findRule(&keys, &inv.data).await {
+        Some(R) => R,
+        None => return Ok(()),
+    };
+
+    let required: Permissions = rule.permissions.iter()
+                                                .filter_map(|p| {
+                                                    let flag: Option<Permissions> = Permissions::from_name(p);
+                                                    if flag.is_none() {
+                                                        println!("Unknown Permission in config: {}", p);
+                                                    }
+                                                    flag
+                                                })
+                                                .fold(Permissions::empty(), |acc, p| acc | p);
+
+    // An empty rule in the config opens the command up for everyone
+    if rule.users.is_empty() && rule.roles.is_empty() && required.is_empty() {
+        return Ok(());
+    }
+
+    if rule.users.contains(&inv.author.get()) {
+        return Ok(());
+    }
+
+    if let Some(member) = getMember(inv).await {
+
+        if member.roles.iter().any(|r: &RoleId| rule.roles.contains(&r.get())) {
+            return Ok(());
+        }
+
+        if !required.is_empty() && member.permissions.contains(required) {
+            return Ok(());
+        }
+    }
+
+    Err(RinrError::Permission {
+        command: key,
+        permissions: required.get_permission_names().iter().map(|p| p.to_string()).collect(),
+        roles: rule.roles,
+    })
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Gets the first matching rule, configured rules take priority over the defaults
+async fn findRule(keys: &[String], data: &RwLock<TypeMap>) -> Option<(String, PermissionRule)> {
+
+    let config: Arc<Mutex<RinrOptions>> = data.read().await.get::<RinrOptions>()?.clone();
+    let config = config.lock().unwrap();
+
+    for key in keys {
+
+        if let Some(rule) = config.permissions.get(key) {
+            return Some((key.clone(), rule.clone()));
+        }
+
+        if let Some((_, perms)) = DEFAULT_RULES.iter().find(|(k, _)| k == key) {
+            return Some((key.clone(), PermissionRule {
+                permissions: perms.iter().map(|p| p.to_string()).collect(),
+                ..PermissionRule::default()
+            }));
+        }
+    }
+
+    None
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Gets the member that sent a message, None in DMs
+async fn getMember(inv: &Invocation) -> Option<MemberInfo> {
+
+    let guild_id = inv.guild_id?;
+
+    match inv.gateway.member(guild_id, inv.author).await {
+        Ok(M) => M,
+        Err(E) => {
+            println!("Unable to get Member: {:?}", E);
+            None
+        },
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::parseArgs;
+    use crate::command::findCommand;
+    use crate::gateway::MemoryGateway;
+    use serenity::all::{GuildId, UserId};
+
+    async fn allowed(input: &str, permissions: Permissions) -> bool {
+
+        let gateway: Arc<MemoryGateway> = Arc::new(MemoryGateway::default());
+        gateway.members.lock().unwrap().insert((GuildId::new(1), UserId::new(2)), MemberInfo { name: "user".to_string(), roles: vec![], permissions });
+
+        let mut data: TypeMap = TypeMap::new();
+        data.insert::<RinrOptions>(Arc::new(Mutex::new(RinrOptions::default())));
+
+        let (name, rest): (&str, &str) = input.split_once(' ').unwrap_or((input, ""));
+        let cmd: &CommandSpec = findCommand(name).unwrap();
+        let args: ParsedArgs = parseArgs(cmd.args, rest).unwrap();
+
+        let inv: Invocation = gateway.invocation(Arc::new(RwLock::new(data)), 1, 2, input);
+
+        checkPermission(cmd, &args, &inv).await.is_ok()
+    }
+
+    #[tokio::test]
+    async fn showingIsOpenSettingIsNot() {
+        assert!(allowed("volume", Permissions::empty()).await);
+        assert!(!allowed("volume 50", Permissions::empty()).await);
+        assert!(allowed("volume 50", Permissions::MANAGE_GUILD).await);
+
+        assert!(allowed("sounds", Permissions::empty()).await);
+        assert!(!allowed("sounds outro off", Permissions::empty()).await);
+        assert!(!allowed("sounds delay=2", Permissions::empty()).await);
+    }
+}
@@ -1,10 +1,10 @@
 
 
 
-use serenity::prelude::Context;
-use serenity::model::prelude::Message;
+use crate::error::RinrError;
+use crate::gateway::Invocation;
 
 
-pub async fn runPoll(_msg: &Message, _ctx: &Context) {
-    ()
+pub async fn runPoll(_inv: &Invocation) -> Result<(), RinrError> {
+    Ok(())
 }
@@ -2,11 +2,12 @@ use std::collections::HashMap;
 use std::io::Write;
 use std::{fs, fs::File};
 
-use serenity::model::prelude::Message;
-use serenity::prelude::Context;
-
-use crate::command::{PREDICTION, User};
-use crate::helper::{buildTxtPath, say};
+use crate::args::{ArgError, Expected, ParsedArgs};
+use crate::command::User;
+use crate::error::RinrError;
+use crate::gateway::Invocation;
+use crate::helper::buildTxtPath;
+use crate::lang::{tr, Lang};
 
 
 //--------------------------------------------------------------------------------------------------------------------------
@@ -22,24 +23,13 @@ pub struct UserPrediction {
 
 //--------------------------------------------------------------------------------------------------------------------------
 // Matches the subversions of the prediction command
-pub async fn addPrediction(msg: &Message, ctx: &Context) {
+pub async fn addPrediction(inv: &Invocation, args: &ParsedArgs) -> Result<(), RinrError> {
     
-    let content: String = msg.content.clone();
-    let mut cmd: i8 = 0;
-
-    if content.contains(" -list") {
-        cmd = 1;
-    } else if content.contains(" -add") {
-        cmd = 2;
-    } else if content.contains(" -remove") {
-        cmd = 3;
-    }
-
-    match cmd {
-        1 => printList(&msg, &ctx).await,
-        2 => insertUser(&msg, &ctx).await,
-        3 => removePrediction(&msg, &ctx).await, // Require admin permissions
-        _ => (),
+    match args.text("mode").unwrap_or("") {
+        "list" => printList(inv).await,
+        "add" => insertUser(inv, args).await,
+        "remove" => removePrediction(inv, args).await, // Require admin permissions
+        _ => Ok(()),
     }
 
 }
@@ -47,69 +37,62 @@ pub async fn addPrediction(msg: &Message, ctx: &Context) {
 
 //--------------------------------------------------------------------------------------------------------------------------
 // Removes Prediction at provided ID
-async fn removePrediction(msg: &Message, ctx: &Context) {
-    let mut u_data = ctx.data.write().await;                  // Waits for Lock Queue on write command and then proceeds with execution 
-    let u_map: &mut HashMap<u64, UserPrediction> = u_data.get_mut::<User>().unwrap();    // Gets mutable reference to the data and stores it in counter
+async fn removePrediction(inv: &Invocation, args: &ParsedArgs) -> Result<(), RinrError> {
 
-    let content: String = checkMessageValid(&msg).await;
+    let content: &str = args.text("text").unwrap_or("");
     if content.is_empty() {
-        return;
+        return Ok(());
     }
 
-    let m_id: u64 = content.parse::<u64>().expect("Unable to parse u64");
-
-    u_map.remove(&m_id);
+    let m_id: u64 = match content.parse::<u64>() {
+        Ok(I) => I,
+        Err(_) => return Err(ArgError::invalid("text", content, Expected::Id).into()),
+    };
 
-    writeToFile(u_map);
-}
+    let mut u_data = inv.data.write().await;                  // Waits for Lock Queue on write command and then proceeds with execution 
+    let u_map: &mut HashMap<u64, UserPrediction> = u_data.get_mut::<User>().unwrap();    // Gets mutable reference to the data and stores it in counter
 
+    u_map.remove(&m_id);
 
-//--------------------------------------------------------------------------------------------------------------------------
-// Checks if a message is not empty to add as a prediction
-async fn checkMessageValid(msg: &Message) -> String {
-
-    let result: String = msg.content.clone()
-                                    .replace(PREDICTION, "")
-                                    .replace("-add ", "")
-                                    .replace("-remove ", "");
-    result
+    writeToFile(u_map)
 }
 
 
 //--------------------------------------------------------------------------------------------------------------------------
 // Prints all current predictions as a discord message
-async fn printList(msg: &Message, ctx: &Context) {
+async fn printList(inv: &Invocation) -> Result<(), RinrError> {
     let mut out: String = String::new();
+    let lang: Lang = inv.lang().await;
 
-    let mut u_data = ctx.data.write().await;                  // Waits for Lock Queue on write command and then proceeds with execution 
+    let mut u_data = inv.data.write().await;                  // Waits for Lock Queue on write command and then proceeds with execution 
     let u_map: &mut HashMap<u64, UserPrediction> = u_data.get_mut::<User>().unwrap();    // Gets mutable reference to the data and stores it in counter
 
-    if u_map.is_empty() {out.push_str("No Predictions at the moment!")} 
-    else {out.push_str("Current Predictions: \n")} 
+    if u_map.is_empty() {out.push_str(&tr(lang, "predict.empty", &[]))} 
+    else {out.push_str(&tr(lang, "predict.title", &[])); out.push('\n')} 
 
     for (key, value) in u_map.iter() {
-        out.push_str(&value.prediction);
-        out.push_str(" by ");
-        out.push_str(&value.user_name);
-        out.push_str(", ID:");
-        out.push_str(key.to_string().as_str());
-        out.push_str("\n");
+        out.push_str(&tr(lang, "predict.entry", &[("prediction", &value.prediction), ("user", &value.user_name), ("id", &key.to_string())]));
+        out.push('\n');
     }
 
-    say(msg, ctx, out).await;
+    drop(u_data);
+
+    inv.say(out).await;
+
+    Ok(())
 }
 
 
 //--------------------------------------------------------------------------------------------------------------------------
 // Adds user to user struct
-async fn insertUser(msg: &Message, ctx: &Context) {  
+async fn insertUser(inv: &Invocation, args: &ParsedArgs) -> Result<(), RinrError> {  
 
-    let prediction: String = checkMessageValid(&msg).await;
+    let prediction: String = args.text("text").unwrap_or("").to_string();
     if prediction.is_empty() {
-        return;
+        return Ok(());
     }
 
-    let mut u_data = ctx.data.write().await;                  // Waits for Lock Queue on write command and then proceeds with execution 
+    let mut u_data = inv.data.write().await;                  // Waits for Lock Queue on write command and then proceeds with execution 
     let u_map: &mut HashMap<u64, UserPrediction> = u_data.get_mut::<User>().unwrap();    // Gets mutable reference to the data and stores it in counter
     
     let mut highest_id: u64 = 0;
@@ -119,24 +102,28 @@ async fn insertUser(msg: &Message, ctx: &Context) {
     }
 
     let key: u64 = highest_id + 1;
-    let p_struct: UserPrediction = UserPrediction {user_id: msg.author.id.get(),
-                                                    prediction: prediction,
-                                                    user_name: msg.author.name.clone()};
+    let p_struct: UserPrediction = UserPrediction {user_id: inv.author.get(),
+                                                    prediction,
+                                                    user_name: inv.author_name.clone()};
 
     
     u_map.insert(key, p_struct);                             // Inserts Element into Map
     println!("Full Map: {:#?}", u_map);
 
-    writeToFile(u_map);
+    writeToFile(u_map)?;
 
-    say(msg, ctx, "Added Prediction".to_string()).await;
+    drop(u_data);
+
+    inv.reply("predict.added", &[]).await;
+
+    Ok(())
 
 }
 
 
 //--------------------------------------------------------------------------------------------------------------------------
 // Writes to File
-fn writeToFile(u_map: &mut HashMap<u64, UserPrediction>) {
+fn writeToFile(u_map: &mut HashMap<u64, UserPrediction>) -> Result<(), RinrError> {
     
     let filepath: String = buildTxtPath();
 
@@ -145,23 +132,22 @@ fn writeToFile(u_map: &mut HashMap<u64, UserPrediction>) {
         Err(_) => println!("Couldn't delete Struct File!"),
     }
 
-    let mut file: File  = match File::create(&filepath) {
-        Ok(O) => O,
-        Err(_) => return,
-    };
+    let mut file: File = File::create(&filepath)?;
 
     for user in u_map {
         
         let mut test: String = user.0.to_string();
-        test.push_str("=");
+        test.push('=');
         test.push_str(user.1.user_id.to_string().as_str());
-        test.push_str("=");
+        test.push('=');
         test.push_str(user.1.prediction.as_str());
-        test.push_str("=");
+        test.push('=');
         test.push_str(user.1.user_name.as_str());
-        test.push_str("\n");
+        test.push('\n');
 
-        file.write_all(test.as_bytes()).expect("Couldn't write to File!");
+        file.write_all(test.as_bytes())?;
 
     }
+
+    Ok(())
 }
\ No newline at end of file
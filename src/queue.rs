@@ -0,0 +1,219 @@
+/*
+    Playback queue of every guild
+
+    the bot can only be in one channel per guild, so sounds that come in while something is playing wait here
+    the first sound starts a runner in the background that plays everything in order, moving to the channel of each sound,
+    and leaves once the queue is empty, sounds that waited longer than the configured time are dropped instead of played late
+*/
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serenity::all::{ChannelId, GuildId};
+use serenity::prelude::{RwLock, TypeMap, TypeMapKey};
+
+use crate::error::RinrError;
+use crate::gateway::Gateway;
+use crate::helper::{QueueOptions, RinrOptions};
+use crate::voice::guildVolume;
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Struct Declaration
+
+pub struct SoundQueue;
+
+impl TypeMapKey for SoundQueue {
+    type Value = HashMap<GuildId, GuildQueue>;
+}
+
+#[derive(Debug, Default)]
+pub struct GuildQueue {
+    pending: VecDeque<QueuedSound>,
+    running: bool,                  // A runner is playing the queue right now
+}
+
+#[derive(Debug)]
+struct QueuedSound {
+    channel: ChannelId,
+    path: String,
+    volume: f32,
+    queued: Instant,
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Queues a sound and starts playing the queue if nothing is playing in the guild yet, returns false if the queue is full
+// Returns right away, the sound plays in the background
+pub async fn queueSound(gateway: &Arc<dyn Gateway>, data: &Arc<RwLock<TypeMap>>, guild_id: GuildId, channel_id: ChannelId, path: String) -> Result<bool, RinrError> {
+
+    let options: QueueOptions = match data.read().await.get::<RinrOptions>() {
+        Some(C) => C.lock().unwrap().queue.clone(),
+        None => QueueOptions::default(),
+    };
+
+    let sound: QueuedSound = QueuedSound {
+        channel: channel_id,
+        path,
+        volume: guildVolume(data, guild_id).await,
+        queued: Instant::now(),
+    };
+
+    {
+        let mut u_data = data.write().await;
+        let queue: &mut GuildQueue = u_data.entry::<SoundQueue>()
+                                           .or_insert_with(HashMap::new)
+                                           .entry(guild_id)
+                                           .or_default();
+
+        if queue.pending.len() >= options.max_length {
+            println!("Queue in {} is full, dropping {}", guild_id, sound.path);
+            return Ok(false);
+        }
+
+        queue.pending.push_back(sound);
+
+        // The runner that is already there picks it up
+        if queue.running {
+            return Ok(true);
+        }
+
+        queue.running = true;
+    }
+
+    tokio::spawn(playQueue(gateway.clone(), data.clone(), guild_id, Duration::from_secs(options.max_wait)));
+
+    Ok(true)
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Plays sounds until the queue of the guild is empty and leaves the call
+async fn playQueue(gateway: Arc<dyn Gateway>, data: Arc<RwLock<TypeMap>>, guild_id: GuildId, max_wait: Duration) {
+
+    loop {
+        match nextSound(&data, guild_id, max_wait).await {
+            Some(S) => if let Err(E) = gateway.play(guild_id, S.channel, S.path.clone(), S.volume).await {
+                println!("Unable to play {} in {}: {}", S.path, guild_id, E);
+            },
+            None => {
+                if let Err(E) = gateway.leave(guild_id).await {
+                    println!("Unable to leave voice in {}: {}", guild_id, E);
+                }
+
+                // Something could have been queued while leaving, it would wait for nothing otherwise
+                let mut u_data = data.write().await;
+                let queue: Option<&mut GuildQueue> = u_data.get_mut::<SoundQueue>().and_then(|q| q.get_mut(&guild_id));
+
+                match queue {
+                    Some(Q) if !Q.pending.is_empty() => continue,
+                    Some(Q) => Q.running = false,
+                    None => (),
+                }
+
+                return;
+            },
+        }
+    }
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Takes the next sound that hasn't waited for too long
+async fn nextSound(data: &RwLock<TypeMap>, guild_id: GuildId, max_wait: Duration) -> Option<QueuedSound> {
+
+    let mut u_data = data.write().await;
+    let queue: &mut GuildQueue = u_data.get_mut::<SoundQueue>()?.get_mut(&guild_id)?;
+
+    while let Some(sound) = queue.pending.pop_front() {
+
+        if sound.queued.elapsed() <= max_wait {
+            return Some(sound);
+        }
+
+        println!("Dropping {} in {}, it waited for {:?}", sound.path, guild_id, sound.queued.elapsed());
+    }
+
+    None
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Throws away everything that is waiting in a guild, the current sound is left to the caller
+pub async fn clearQueue(data: &RwLock<TypeMap>, guild_id: GuildId) {
+
+    if let Some(Q) = data.write().await.get_mut::<SoundQueue>().and_then(|q| q.get_mut(&guild_id)) {
+        Q.pending.clear();
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gateway::{MemoryGateway, Outgoing};
+
+    fn sound(path: &str, waited: u64) -> QueuedSound {
+        QueuedSound {
+            channel: ChannelId::new(1),
+            path: path.to_string(),
+            volume: 1.0,
+            queued: Instant::now().checked_sub(Duration::from_secs(waited)).unwrap(),
+        }
+    }
+
+    #[tokio::test]
+    async fn nextSoundKeepsOrderAndDropsStale() {
+
+        let guild_id: GuildId = GuildId::new(1);
+        let queue: GuildQueue = GuildQueue { pending: VecDeque::from([sound("old", 60), sound("a", 1), sound("stale", 31), sound("b", 0)]), running: true };
+
+        let mut data: TypeMap = TypeMap::new();
+        data.insert::<SoundQueue>(HashMap::from([(guild_id, queue)]));
+        let data: RwLock<TypeMap> = RwLock::new(data);
+
+        let max_wait: Duration = Duration::from_secs(30);
+
+        assert_eq!(nextSound(&data, guild_id, max_wait).await.map(|s| s.path), Some("a".to_string()));
+        assert_eq!(nextSound(&data, guild_id, max_wait).await.map(|s| s.path), Some("b".to_string()));
+        assert!(nextSound(&data, guild_id, max_wait).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn queuePlaysInTheBackground() {
+
+        let memory: Arc<MemoryGateway> = Arc::new(MemoryGateway::default());
+        let gateway: Arc<dyn Gateway> = memory.clone();
+        let data: Arc<RwLock<TypeMap>> = Arc::new(RwLock::new(TypeMap::new()));
+        let guild_id: GuildId = GuildId::new(1);
+
+        // Nothing runs until the test yields, so both end up in the queue of the first runner
+        assert!(queueSound(&gateway, &data, guild_id, ChannelId::new(2), "first".to_string()).await.unwrap());
+        assert!(queueSound(&gateway, &data, guild_id, ChannelId::new(3), "second".to_string()).await.unwrap());
+        assert!(memory.take().is_empty());
+
+        while data.read().await.get::<SoundQueue>().unwrap()[&guild_id].running {
+            tokio::task::yield_now().await;
+        }
+
+        assert_eq!(memory.take(), vec![
+            Outgoing::Play(guild_id, ChannelId::new(2), "first".to_string(), 1.0),
+            Outgoing::Play(guild_id, ChannelId::new(3), "second".to_string(), 1.0),
+            Outgoing::Leave(guild_id),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn fullQueueRefuses() {
+
+        let gateway: Arc<dyn Gateway> = Arc::new(MemoryGateway::default());
+        let data: Arc<RwLock<TypeMap>> = Arc::new(RwLock::new(TypeMap::new()));
+        let guild_id: GuildId = GuildId::new(1);
+
+        for i in 0..QueueOptions::default().max_length {
+            assert!(queueSound(&gateway, &data, guild_id, ChannelId::new(2), i.to_string()).await.unwrap());
+        }
+
+        assert!(!queueSound(&gateway, &data, guild_id, ChannelId::new(2), "one more".to_string()).await.unwrap());
+    }
+}
@@ -1,30 +1,37 @@
 use rand::{SeedableRng, rngs::StdRng, seq::SliceRandom};
 
-use serenity::all::{Context, Message, UserId};
+use serenity::all::UserId;
 
 
+use crate::args::ParsedArgs;
+use crate::error::RinrError;
+use crate::gateway::Invocation;
 use crate::helper::Santa;
+use crate::lang::{tr, Lang};
 
 
 
 
 
-pub async fn santaHandler(msg: &Message, ctx: &Context) {
+pub async fn santaHandler(inv: &Invocation, args: &ParsedArgs) -> Result<(), RinrError> {
     
+    // Has to be looked up before the write lock is taken
+    let lang: Lang = inv.lang().await;
+
     // Aquire lock for global data
-    let mut u_data = ctx.data.write().await;
+    let mut u_data = inv.data.write().await;
     let santa: &mut Santa = u_data.get_mut::<Santa>().expect("No Santa Vector Available");
 
-    let m: String = msg.content.clone();
+    let m: &str = args.text("mode").unwrap_or("");
     
-    if m.contains("register") {
-        santa.members.insert(msg.author.id.clone());
-    } else if m.contains("remove") {
-        santa.members.remove(&msg.author.id);
-    } else if m.contains("start") {
+    if m == "register" {
+        santa.members.insert(inv.author);
+    } else if m == "remove" {
+        santa.members.remove(&inv.author);
+    } else if m == "start" {
 
         if santa.members.len() < 2 {
-            return;
+            return Ok(());
         }
 
         println!("Current Members: {:#?}", santa);
@@ -33,8 +40,8 @@ pub async fn santaHandler(msg: &Message, ctx: &Context) {
         let mut gifter: Vec<UserId> = Vec::with_capacity(8);
         let mut giftee: Vec<UserId> = Vec::with_capacity(8);
 
-        gifter.extend(santa.members.clone().into_iter());
-        giftee.extend(santa.members.clone().into_iter());
+        gifter.extend(santa.members.clone());
+        giftee.extend(santa.members.clone());
 
         let mut rng = StdRng::from_entropy();
 
@@ -47,8 +54,18 @@ pub async fn santaHandler(msg: &Message, ctx: &Context) {
             }
         }
 
+        // Everyone still gets their DM if one of them fails, the last failure is reported
+        let mut failed: Option<RinrError> = None;
+
         for (gift, rec) in gifter.iter().zip(giftee.iter()) {
-            dmUser(gift, rec, ctx).await;
+            if let Err(E) = dmUser(gift, rec, inv, lang).await {
+                println!("Failed to send message to {}: {}", gift, E);
+                failed = Some(E);
+            }
+        }
+
+        if let Some(E) = failed {
+            return Err(E);
         }
 
 
@@ -56,18 +73,16 @@ pub async fn santaHandler(msg: &Message, ctx: &Context) {
         println!("Current Members: {:#?}", santa);
     }
 
+    Ok(())
 }
 
-async fn dmUser(gift: &UserId, rec: &UserId, ctx: &Context) {
+async fn dmUser(gift: &UserId, rec: &UserId, inv: &Invocation, lang: Lang) -> Result<(), RinrError> {
 
-    let msg = format!("Your Secret Santa is <@{}>", rec);
+    let msg = tr(lang, "santa.assigned", &[("user", &rec.to_string())]);
 
-    if let Ok(user) =  gift.to_user(&ctx.http).await {
-        if let Ok(channel) = user.create_dm_channel(&ctx.http).await {
-            match channel.say(&ctx.http, msg).await {
-                Ok(_) =>println!("Successfully sent message to user {} -> {}", user.id, rec.get()),
-                Err(_) => println!("Failed to send message to {}", user.name),
-            }
-        }        
-    }
+    inv.gateway.dm(*gift, msg).await?;
+
+    println!("Successfully sent message to user {} -> {}", gift, rec.get());
+
+    Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,320 @@
+/*
+    Console simulator, started with --simulate
+
+    reads commands from stdin as a fake user in a fake guild and runs them through the normal command path
+    replies, DMs and voice plays go to a MemoryGateway that prints them, the event thread runs on a FakeClock
+    the config, sound and prediction files in the working directory are used like in a normal run
+
+    options: --user <id> --name <name> --guild <id> --channel <id> --bot <id>
+    lines starting with : control the simulation, see :help
+*/
+use std::io::BufRead;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use chrono::Local;
+use serenity::all::{ChannelId, GuildId, MessageId, Permissions, UserId};
+use serenity::prelude::{RwLock, TypeMap};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+use crate::args::parseDuration;
+use crate::clock::{Clock, ClockKey, FakeClock};
+use crate::command::{checkCommand, executeCommand, User};
+use crate::cooldown::Cooldowns;
+use crate::event::loops;
+use crate::gateway::{AttachmentInfo, Gateway, Invocation, MemberInfo, MemoryGateway};
+use crate::intro::{readIntros, IntroLibrary};
+use crate::helper::{fillStruct, readConfig, DailyEventSignalKey, EventSignal, RinrOptions, Santa};
+use crate::voice::{voiceEvent, BotId};
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Const Declaration
+
+const HELP: &str = ":time                 show the simulated time
+:advance <duration>   move the clock forward, like 90, 1:30 or 2h
+:join <channel>       join or move to a voice channel and play the intro or move sound
+:leave                leave the voice channel and play the outro
+:attach <url>         attach a file to the next message
+:user <id> [name]     switch to another user
+:quit                 stop the simulation
+anything else is sent as a message";
+
+// User of the simulated bot unless --bot is given, :user switches to it to see what the bot ignores
+const SIM_BOT: u64 = 1000;
+
+// How long :advance waits for the event thread to catch up before giving the prompt back
+const SETTLE_TIMEOUT: Duration = Duration::from_secs(5);
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Struct Declaration
+
+struct Session {
+    gateway: Arc<MemoryGateway>,
+    data: Arc<RwLock<TypeMap>>,
+    clock: Arc<FakeClock>,
+    user: UserId,
+    name: String,
+    guild: GuildId,
+    channel: ChannelId,
+    next_message: u64,
+    attachment: Option<AttachmentInfo>,     // Sent with the next message
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Runs the simulation until stdin is closed or :quit is entered
+pub async fn simulate(args: Vec<String>) {
+
+    let mut session: Session = match buildSession(&args).await {
+        Some(S) => S,
+        None => {
+            println!("Usage: --simulate [--user <id>] [--name <name>] [--guild <id>] [--channel <id>] [--bot <id>]");
+            return;
+        },
+    };
+
+    println!("Simulating as {} ({}) in guild {}, channel {}", session.name, session.user, session.guild, session.channel);
+    println!("{}", HELP);
+
+    let mut lines: UnboundedReceiver<String> = readStdin();
+
+    while let Some(line) = lines.recv().await {
+
+        let line: &str = line.trim();
+
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(control) = line.strip_prefix(':') {
+            if !session.control(control).await {
+                break;
+            }
+        } else {
+            session.message(line).await;
+        }
+
+        // Everything was printed as it was sent already
+        session.gateway.take();
+    }
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Sets up the shared data and the event thread like a discord connection would
+async fn buildSession(args: &[String]) -> Option<Session> {
+
+    let user: UserId = UserId::new(option(args, "--user").unwrap_or(Ok(1)).ok()?);
+    let guild: GuildId = GuildId::new(option(args, "--guild").unwrap_or(Ok(1)).ok()?);
+    let channel: ChannelId = ChannelId::new(option(args, "--channel").unwrap_or(Ok(1)).ok()?);
+    let bot: UserId = UserId::new(option(args, "--bot").unwrap_or(Ok(SIM_BOT)).ok()?);
+
+    let name: String = args.iter()
+                           .position(|a| a == "--name")
+                           .and_then(|i| args.get(i + 1))
+                           .cloned()
+                           .unwrap_or("tester".to_string());
+
+    let gateway: Arc<MemoryGateway> = Arc::new(MemoryGateway { echo: true, ..MemoryGateway::default() });
+    let clock: Arc<FakeClock> = Arc::new(FakeClock::new(Local::now().naive_local()));
+
+    let config: RinrOptions = readConfig().await;
+    let (send, recv): (Sender<EventSignal>, Receiver<EventSignal>) = mpsc::channel();
+
+    let mut data: TypeMap = TypeMap::new();
+
+    data.insert::<Cooldowns>(Arc::new(Mutex::new(Cooldowns::new(config.concurrency))));
+
+    let config: Arc<Mutex<RinrOptions>> = Arc::new(Mutex::new(config));
+    let thread_config: Arc<Mutex<RinrOptions>> = config.clone();
+    let thread_gateway: Arc<dyn Gateway> = gateway.clone();
+    let thread_clock: Arc<dyn Clock> = clock.clone();
+
+    thread::spawn(move || {
+        loops(thread_config, recv, thread_gateway, thread_clock);
+    });
+
+    data.insert::<DailyEventSignalKey>(send);
+    data.insert::<RinrOptions>(config);
+    data.insert::<ClockKey>(clock.clone());
+    data.insert::<User>(fillStruct());
+    data.insert::<Santa>(Santa::default());
+    data.insert::<IntroLibrary>(readIntros());
+    data.insert::<BotId>(bot);
+
+    let session: Session = Session {
+        gateway,
+        data: Arc::new(RwLock::new(data)),
+        clock,
+        user,
+        name,
+        guild,
+        channel,
+        next_message: 1,
+        attachment: None,
+    };
+
+    session.addMember();
+
+    Some(session)
+}
+
+
+impl Session {
+
+    // Runs a line as if it was sent in the channel
+    async fn message(&mut self, content: &str) {
+
+        let inv: Invocation = Invocation {
+            gateway: self.gateway.clone(),
+            data: self.data.clone(),
+            author: self.user,
+            author_name: self.name.clone(),
+            channel_id: self.channel,
+            guild_id: Some(self.guild),
+            message_id: Some(MessageId::new(self.next_message)),
+            content: content.to_string(),
+            attachments: self.attachment.take().into_iter().collect(),
+        };
+
+        self.next_message += 1;
+
+        match checkCommand(content).await {
+            Some(C) => executeCommand(C, &inv).await,
+            None => println!("(not a command)"),
+        }
+    }
+
+    // Handles a : line, returns false once the simulation should stop
+    async fn control(&mut self, line: &str) -> bool {
+
+        let mut parts = line.split_whitespace();
+
+        match (parts.next().unwrap_or(""), parts.next()) {
+            ("quit", _) | ("q", _) => return false,
+            ("help", _) => println!("{}", HELP),
+            ("time", _) => println!("{}", self.clock.now().format("%d/%m/%Y %H:%M:%S")),
+            ("advance", Some(D)) => match parseDuration(D) {
+                Some(S) => self.advance(S).await,
+                None => println!("Not a duration: {}", D),
+            },
+            ("join", Some(C)) => match C.parse::<u64>() {
+                Ok(C) if C != 0 => self.join(ChannelId::new(C)).await,
+                _ => println!("Not a channel: {}", C),
+            },
+            ("attach", Some(U)) => {
+                self.attachment = Some(AttachmentInfo {
+                    url: U.to_string(),
+                    filename: U.rsplit('/').next().unwrap_or(U).to_string(),
+                    size: 0,
+                });
+                println!("The next message has {} attached", U);
+            },
+            ("leave", _) => self.leave().await,
+            ("user", Some(U)) => match U.parse::<u64>() {
+                Ok(U) if U != 0 => {
+                    self.user = UserId::new(U);
+                    self.name = parts.next().unwrap_or("tester").to_string();
+                    self.addMember();
+                    println!("Now simulating as {} ({})", self.name, self.user);
+                },
+                _ => println!("Not a user: {}", U),
+            },
+            _ => println!("Unknown control, try :help"),
+        }
+
+        true
+    }
+
+    // Moves the clock and waits until the event thread ran everything that became due
+    async fn advance(&self, secs: u64) {
+
+        self.clock.advance(secs);
+
+        let mut waited: Duration = Duration::ZERO;
+
+        while !self.clock.caughtUp() && waited < SETTLE_TIMEOUT {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            waited += Duration::from_millis(50);
+        }
+
+        println!("Time is now {}", self.clock.now().format("%d/%m/%Y %H:%M:%S"));
+    }
+
+    // Joins a voice channel like the voice state handler would see it
+    async fn join(&self, channel: ChannelId) {
+
+        let previous: Option<ChannelId> = self.gateway.voice.lock().unwrap().insert((self.guild, self.user), channel);
+
+        println!("{} joined voice {}", self.name, channel);
+
+        self.voiceEvent(previous, Some(channel)).await;
+    }
+
+    // Leaves voice like the voice state handler would see it
+    async fn leave(&self) {
+
+        let previous: Option<ChannelId> = self.gateway.voice.lock().unwrap().remove(&(self.guild, self.user));
+
+        println!("{} left voice", self.name);
+
+        self.voiceEvent(previous, None).await;
+    }
+
+    async fn voiceEvent(&self, old: Option<ChannelId>, new: Option<ChannelId>) {
+        let gateway: Arc<dyn Gateway> = self.gateway.clone();
+
+        match voiceEvent(&gateway, &self.data, self.guild, self.user.get(), old, new).await {
+            Ok(true) => (),
+            Ok(false) => println!("(no sound)"),
+            Err(E) => println!("Unable to play sound for {}: {}", self.user, E),
+        }
+    }
+
+    // The simulated user may use every command
+    fn addMember(&self) {
+        self.gateway.members.lock().unwrap().insert((self.guild, self.user), MemberInfo {
+            name: self.name.clone(),
+            roles: vec![],
+            permissions: Permissions::all(),
+        });
+    }
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Parses the value after a flag, None if the flag isn't there
+fn option(args: &[String], flag: &str) -> Option<Result<u64, ()>> {
+
+    let i: usize = args.iter().position(|a| a == flag)?;
+
+    Some(args.get(i + 1)
+             .and_then(|v| v.parse::<u64>().ok())
+             .filter(|v| *v != 0)
+             .ok_or(()))
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Reads stdin on its own thread so the runtime isn't blocked while waiting for input
+fn readStdin() -> UnboundedReceiver<String> {
+
+    let (send, recv): (UnboundedSender<String>, UnboundedReceiver<String>) = unbounded_channel();
+
+    thread::spawn(move || {
+        for line in std::io::stdin().lock().lines() {
+            match line {
+                Ok(L) => if send.send(L).is_err() {
+                    break;
+                },
+                Err(_) => break,
+            }
+        }
+    });
+
+    recv
+}
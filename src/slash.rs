@@ -0,0 +1,176 @@
+use serenity::all::{Command, CommandDataOptionValue, CommandInteraction, CommandOptionType,
+                    CreateCommand, CreateCommandOption, CreateInteractionResponse,
+                    CreateInteractionResponseMessage};
+use serenity::prelude::Context;
+
+use crate::args::{quoteValue, ArgError, ArgKind, ArgReason, Render};
+use crate::command::{checkCommand, executeCommand, formatName, CommandSpec, REGISTRY};
+use crate::gateway::Invocation;
+use crate::helper::guildLang;
+use crate::lang::{describe, Lang};
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Const Declaration
+
+// Discord rejects the whole bulk registration if a single command or option description is longer
+pub const MAX_DESCRIPTION: usize = 100;
+
+// Checked when building, counts bytes so it is stricter than discord for anything that isn't ascii
+const _: () = {
+    let mut c: usize = 0;
+
+    while c < REGISTRY.len() {
+        assert!(REGISTRY[c].usage.len() <= MAX_DESCRIPTION, "a command usage is longer than discord allows");
+
+        let mut a: usize = 0;
+
+        while a < REGISTRY[c].args.len() {
+            assert!(REGISTRY[c].args[a].description.len() <= MAX_DESCRIPTION, "an argument description is longer than discord allows");
+            a += 1;
+        }
+
+        c += 1;
+    }
+};
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Registers all slash commands globally
+pub async fn registerCommands(ctx: &Context) {
+
+    let commands: Vec<CreateCommand> = REGISTRY.iter().map(buildCommand).collect();
+
+    match Command::set_global_commands(&ctx.http, commands).await {
+        Ok(C) => println!("Registered {} Slash Commands!", C.len()),
+        Err(E) => println!("Unable to register Slash Commands: {:?}", E),
+    }
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Builds a discord command from its registry entry
+fn buildCommand(cmd: &CommandSpec) -> CreateCommand {
+
+    let options: Vec<CreateCommandOption> = cmd.args.iter().map(|o| {
+
+        let mut option: CreateCommandOption = CreateCommandOption::new(optionType(o.kind), o.name, o.description)
+                                                                    .description_localized("de", describe(Lang::De, &format!("{}.{}", cmd.name, o.name), o.description))
+                                                                    .required(o.required);
+
+        for c in o.choices {
+            option = option.add_string_choice(*c, *c);
+        }
+
+        // Timestamps are strings for discord, their range is only checked by the parser
+        if let (ArgKind::Int, Some((min, max))) = (o.kind, o.range) {
+            option = option.min_int_value(min).max_int_value(max);
+        }
+
+        option
+    }).collect();
+
+    CreateCommand::new(cmd.name)
+        .description(cmd.usage)
+        .description_localized("de", describe(Lang::De, cmd.name, cmd.usage))
+        .set_options(options)
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Maps argument kinds to discord option types
+fn optionType(kind: ArgKind) -> CommandOptionType {
+    match kind {
+        ArgKind::Int => CommandOptionType::Integer,
+        ArgKind::Float => CommandOptionType::Number,
+        ArgKind::Bool => CommandOptionType::Boolean,
+        ArgKind::User => CommandOptionType::User,
+        ArgKind::Text | ArgKind::Timestamp | ArgKind::Date | ArgKind::Time | ArgKind::Enum => CommandOptionType::String,
+    }
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Handles a slash command by turning it into its `$` form and running it through the normal command path
+pub async fn slashHandler(ctx: &Context, interaction: &CommandInteraction) {
+
+    let slash: &CommandSpec = match REGISTRY.iter().find(|c| c.name == interaction.data.name) {
+        Some(S) => S,
+        None => {
+            println!("Unknown Slash Command: {}", interaction.data.name);
+            return;
+        },
+    };
+
+    // Interactions have to be acknowledged within 3 seconds, the handlers reply in the channel themselves
+    let content: Result<String, ArgError> = buildContent(slash, interaction);
+
+    let ack: String = match &content {
+        Ok(C) => format!("`{}`", C),
+        Err(E) => E.render(guildLang(&ctx.data, interaction.guild_id).await),
+    };
+
+    println!("{} used slash : {:?}", interaction.user.name, ack);
+
+    let response: CreateInteractionResponse = CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new()
+            .content(ack)
+            .ephemeral(true)
+    );
+
+    if let Err(why) = interaction.create_response(&ctx.http, response).await {
+        println!("Interaction Response failed. Error: {:?}", why);
+    }
+
+    let content: String = match content {
+        Ok(C) => C,
+        Err(_) => return,
+    };
+
+    let command: &CommandSpec = match checkCommand(&content).await {
+        Some(C) => C,
+        None => return,
+    };
+
+    executeCommand(command, &Invocation::fromInteraction(interaction, ctx, content)).await;
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Writes the given options back into the text syntax of the command
+fn buildContent(slash: &CommandSpec, interaction: &CommandInteraction) -> Result<String, ArgError> {
+
+    let mut parts: Vec<String> = vec![];
+
+    // Keeps the declared order so positional values end up where the handlers expect them
+    for o in slash.args {
+
+        let given = interaction.data.options.iter().find(|d| d.name == o.name);
+
+        let value: String = match given.map(|d| &d.value) {
+            Some(CommandDataOptionValue::String(S)) => S.clone(),
+            Some(CommandDataOptionValue::Integer(I)) => I.to_string(),
+            Some(CommandDataOptionValue::Number(N)) => N.to_string(),
+            Some(CommandDataOptionValue::Boolean(B)) => (*B as u8).to_string(),
+            Some(CommandDataOptionValue::User(U)) => U.get().to_string(),
+            _ => continue,
+        };
+
+        parts.push(match o.render {
+            Render::Positional => value,
+            Render::Flag => format!("-{}", value),
+            Render::Switch if value == "1" => o.name.to_string(),
+            Render::Switch => continue,
+            // Typed freely, so they are quoted to keep spaces and brackets inside the value
+            Render::Key | Render::Bracketed => match quoteValue(&value) {
+                Some(Q) => format!("{}={}", o.name, Q),
+                None => return Err(ArgError::new(o.name, ArgReason::Unquotable)),
+            },
+            Render::Mention => format!("<@{}>", value),
+        });
+    }
+
+    parts.insert(0, formatName(slash));
+
+    Ok(parts.join(" "))
+}
@@ -1,61 +1,233 @@
-use std::fmt::Error;
+use std::collections::HashMap;
 use std::path::Path;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
-use serenity::model::prelude::{ChannelId, GuildId, ChannelType, Member, GuildChannel};
+use serenity::model::prelude::{ChannelId, GuildId, UserId};
 use serenity::model::voice::VoiceState;
 use serenity::client::*;
 use songbird::input::{self, Compose};
 use songbird::tracks::TrackHandle;
 use songbird::{Songbird, input::File};
+use serenity::prelude::{RwLock, TypeMap, TypeMapKey};
+use tokio::sync::Notify;
+use chrono::NaiveDateTime;
 
-use mp3_duration;
 
 use crate::helper::*;
+use crate::clock::now;
+use crate::error::RinrError;
+use crate::gateway::{Gateway, Invocation};
+use crate::args::{ArgError, ArgReason, ParsedArgs};
+use crate::intro::{pickIntro, SoundKind};
+use crate::queue::{clearQueue, queueSound};
+use crate::lang::{tr, Lang};
+use crate::args::formatTimestamp;
 
 //--------------------------------------------------------------------------------------------------------------------------
-// Const Declaration
-pub static BOT_ID: u64 = 909567837964746863;
+// Struct Declaration
+
+// Wakes everything that is waiting for a sound to end in a guild once it gets stopped
+pub struct Playback;
+
+impl TypeMapKey for Playback {
+    type Value = HashMap<GuildId, Arc<Notify>>;
+}
+
+// The user of the bot itself, set once it is ready
+pub struct BotId;
+
+impl TypeMapKey for BotId {
+    type Value = UserId;
+}
+
+// When the last sound of each kind of a user was queued in a guild, for the rejoin cooldown
+pub struct LastSounds;
+
+impl TypeMapKey for LastSounds {
+    type Value = HashMap<(GuildId, u64, SoundKind), NaiveDateTime>;
+}
 
 //--------------------------------------------------------------------------------------------------------------------------
-// Joins the Voice channel and plays sound
-pub async fn joinVoice(ctx: Context, old: Option<VoiceState>, new: &VoiceState) -> Result<(), Error> {
+// Joins the Voice channel and plays sound, returns false if there was nothing to play
+pub async fn joinVoice(gateway: &Arc<dyn Gateway>, data: &Arc<RwLock<TypeMap>>, old: Option<VoiceState>, new: &VoiceState) -> Result<bool, RinrError> {
+
+    // Gets id of channel
+    let guild_id: GuildId = match new.guild_id {
+        Some(G) => G,
+        None => return Ok(false),
+    };
+
+    voiceEvent(gateway, data, guild_id, new.user_id.get(), old.and_then(|o| o.channel_id), new.channel_id).await
+}
 
-    let user_id: u64 = new.user_id.get();
 
-    let channel_id = match checkError(user_id, old, &new) {
-        Ok(C) => C,
-        Err(_) => return Err(Error),
+//--------------------------------------------------------------------------------------------------------------------------
+// Plays the intro, outro or move sound for a user going from one channel to another, None is outside of voice
+pub async fn voiceEvent(gateway: &Arc<dyn Gateway>, data: &Arc<RwLock<TypeMap>>, guild_id: GuildId, user_id: u64, old: Option<ChannelId>, new: Option<ChannelId>) -> Result<bool, RinrError> {
+
+    // Checks if user joined is the bot
+    if botId(data).await.is_some_and(|b| b.get() == user_id) {
+        return Ok(false);
+    }
+
+    // Muting and the like keep the channel and play nothing
+    let (kind, channel_id): (SoundKind, ChannelId) = match (old, new) {
+        (None, Some(N)) => (SoundKind::Intro, N),
+        (Some(O), None) => (SoundKind::Outro, O),
+        (Some(O), Some(N)) if O != N => (SoundKind::Move, N),
+        _ => return Ok(false),
     };
 
-    // Gets id of channel
-    let guild_id: GuildId = new.guild_id.unwrap();
+    let options: SoundOptions = match data.read().await.get::<RinrOptions>() {
+        Some(C) => C.lock().unwrap().soundsFor(guild_id.get()),
+        None => SoundOptions::default(),
+    };
 
-    let guild_channels = ctx.http.get_channels(guild_id).await;
+    let enabled: bool = match kind {
+        SoundKind::Intro => options.intros,
+        SoundKind::Outro => options.outros,
+        SoundKind::Move => options.moves,
+    };
+
+    if !enabled {
+        return Ok(false);
+    }
+
+    if kind == SoundKind::Outro {
+
+        if options.outro_delay > 0 {
+            tokio::time::sleep(std::time::Duration::from_millis(options.outro_delay)).await;
+        }
+
+        // Checked after the delay, someone could have come back in the meantime
+        if !options.outro_when_empty && gateway.voiceCount(guild_id, channel_id).await? == 0 {
+            return Ok(false);
+        }
+    }
+
+    if !checkPolicy(gateway.as_ref(), data, kind, guild_id, user_id, channel_id).await? {
+        return Ok(false);
+    }
+
+    let played: bool = playSound(gateway, data, kind, guild_id, user_id, channel_id).await?;
+
+    // Only a sound that made it into the queue starts the cooldown
+    if played {
+        let time: NaiveDateTime = now(data).await;
+        data.write().await.entry::<LastSounds>().or_insert_with(HashMap::new).insert((guild_id, user_id, kind), time);
+    }
+
+    Ok(played)
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Checks the intro policy of the guild, the cooldown is per kind so a move sound doesn't hold up the next intro
+async fn checkPolicy(gateway: &dyn Gateway, data: &RwLock<TypeMap>, kind: SoundKind, guild_id: GuildId, user_id: u64, channel_id: ChannelId) -> Result<bool, RinrError> {
+
+    let policy: IntroPolicy = match data.read().await.get::<RinrOptions>() {
+        Some(C) => C.lock().unwrap().policyFor(guild_id.get()),
+        None => return Ok(true),
+    };
+
+    if policy.denied_channels.contains(&channel_id.get())
+        || (!policy.allowed_channels.is_empty() && !policy.allowed_channels.contains(&channel_id.get())) {
+        println!("Sounds are off in channel {} of {}", channel_id, guild_id);
+        return Ok(false);
+    }
+
+    let time: NaiveDateTime = now(data).await;
+
+    if policy.quiet_hours.iter().any(|q| q.contains(time.time())) {
+        println!("Quiet hours in {}, no sound for {}", guild_id, user_id);
+        return Ok(false);
+    }
+
+    if policy.min_listeners > 0 {
+
+        // The user is still counted unless they just left
+        let count: usize = gateway.voiceCount(guild_id, channel_id).await?;
+        let others: usize = match kind {
+            SoundKind::Outro => count,
+            _ => count.saturating_sub(1),
+        };
+
+        if others < policy.min_listeners {
+            println!("Only {} others in channel {}, no sound for {}", others, channel_id, user_id);
+            return Ok(false);
+        }
+    }
 
-    match checkDuplicate(guild_channels, &ctx.cache).await {
-        false => (),
-        true => return Err(Error),
+    let last: Option<NaiveDateTime> = match data.read().await.get::<LastSounds>() {
+        Some(L) => L.get(&(guild_id, user_id, kind)).copied(),
+        None => None,
     };
 
+    if let Some(L) = last {
+        if (time - L).num_seconds() < policy.rejoin_cooldown as i64 {
+            println!("{} is on cooldown in {}", user_id, guild_id);
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Plays a sound of a user in a channel, returns false if there was nothing to play
+pub async fn playSound(gateway: &Arc<dyn Gateway>, data: &Arc<RwLock<TypeMap>>, kind: SoundKind, guild_id: GuildId, user_id: u64, channel_id: ChannelId) -> Result<bool, RinrError> {
 
-    // Builds path for video
-    let path: String = buildVidPath(user_id.to_string());
+    // Picks one of the sounds of the user
+    let path: String = match pickIntro(data, kind, user_id).await {
+        Some(P) => P,
+        None => return Ok(false),
+    };
 
 
     // Checks if path exists
     match checkVidPath(&path) {
         true => (),
-        false => return Err(Error),
+        false => return Ok(false),
     }
 
+    // Waits for whatever is playing in the guild right now
+    queueSound(gateway, data, guild_id, channel_id, path).await
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Gets the user of the bot, None until it is ready
+pub async fn botId(data: &RwLock<TypeMap>) -> Option<UserId> {
+    data.read().await.get::<BotId>().copied()
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Gets the playback volume of a guild, 1.0 if it has none set
+pub async fn guildVolume(data: &RwLock<TypeMap>, guild_id: GuildId) -> f32 {
+
+    match data.read().await.get::<RinrOptions>() {
+        Some(C) => C.lock().unwrap().volumeFor(guild_id.get()) as f32 / 100.0,
+        None => 1.0,
+    }
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Joins or moves to a voice channel and plays a file until it is done, leaving is up to the queue
+pub async fn playFile(ctx: &Context, guild_id: GuildId, channel_id: ChannelId, path: String, volume: f32) -> Result<(), RinrError> {
+
     // Gets songbird instance
-    let manager = songbird::get(&ctx).await
-                                                    .expect("Unable to get songbird instance!")
-                                                    .clone();
+    let manager = match songbird::get(ctx).await {
+        Some(M) => M.clone(),
+        None => return Err(RinrError::Voice("Unable to get songbird instance".to_string())),
+    };
 
     // Joins the voice channel
-    let _handler = manager.join(guild_id, channel_id).await;
+    if let Err(E) = manager.join(guild_id, channel_id).await {
+        return Err(RinrError::Voice(format!("Unable to join channel {}: {:?}", channel_id, E)));
+    }
     
 
     if let Some(handler_lock) = manager.get(guild_id) {
@@ -75,39 +247,258 @@ pub async fn joinVoice(ctx: Context, old: Option<VoiceState>, new: &VoiceState)
 
         let p_path: &Path = Path::new(p);
 
-        let mut file_source: File<&Path> = input::File::new(&p_path);
+        let mut file_source: File<&Path> = input::File::new(p_path);
+
+        // Registered before playing so a stop right after the start isn't missed
+        let stopped: Arc<Notify> = playbackSignal(ctx, guild_id).await;
+        let interrupted = stopped.notified();
+        tokio::pin!(interrupted);
+        interrupted.as_mut().enable();
 
 
         let _ = match file_source.create_async().await {
             Ok(I) => I,
-            Err(_) => {
+            Err(E) => {
                 removeManager(&manager, guild_id).await;
-                return Err(Error);
+                return Err(RinrError::Voice(format!("Unable to open {}: {:?}", p, E)));
             }
         };        
 
         // Starts playing from source file
-        let _: TrackHandle = handler_lock.lock().await.play_input(file_source.into());
+        let track: TrackHandle = handler_lock.lock().await.play_input(file_source.into());
+
+        if let Err(E) = track.set_volume(volume) {
+            println!("Unable to set volume in {}: {:?}", guild_id, E);
+        }
 
-        // Sleeps for the length of the audio file so that it can play
+        // Sleeps for the length of the audio file so that it can play, STFU and $disconnect cut it short
                 
         /*if let Some(dur) = handler.metadata().duration {
             } */
         
         if let Ok(dur) = mp3_duration::from_path(p_path) {
-            tokio::time::sleep(dur).await;
+            tokio::select! {
+                _ = tokio::time::sleep(dur) => (),
+                _ = &mut interrupted => println!("Playback in {} was stopped", guild_id),
+            }
         } else {
             println!("Unable to fetch duration!");
         }
         
         
     } else {
-        println!("Unexpected error");
-        return Err(Error);
+        return Err(RinrError::Voice(format!("No call for guild {}", guild_id)));
     }
 
+    Ok(())
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Stops every sound in a guild, returns false if the bot isn't in a call there
+pub async fn stopPlaying(ctx: &Context, guild_id: GuildId) -> Result<bool, RinrError> {
+
+    let manager: Arc<Songbird> = match songbird::get(ctx).await {
+        Some(M) => M.clone(),
+        None => return Err(RinrError::Voice("Unable to get songbird instance".to_string())),
+    };
+
+    let handler_lock = match manager.get(guild_id) {
+        Some(H) => H,
+        None => return Ok(false),
+    };
+
+    handler_lock.lock().await.stop();
+
+    interruptPlayback(ctx, guild_id).await;
+
+    Ok(true)
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Stops every sound in a guild and leaves the call, returns false if the bot isn't in a call there
+pub async fn leaveVoice(ctx: &Context, guild_id: GuildId) -> Result<bool, RinrError> {
+
+    let manager: Arc<Songbird> = match songbird::get(ctx).await {
+        Some(M) => M.clone(),
+        None => return Err(RinrError::Voice("Unable to get songbird instance".to_string())),
+    };
+
+    let handler_lock = match manager.get(guild_id) {
+        Some(H) => H,
+        None => return Ok(false),
+    };
+
+    handler_lock.lock().await.stop();
+
+    // Leaves before waking the players so they don't try to leave as well
     removeManager(&manager, guild_id).await;
 
+    interruptPlayback(ctx, guild_id).await;
+
+    Ok(true)
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Gets the signal that is used to stop playback in a guild
+async fn playbackSignal(ctx: &Context, guild_id: GuildId) -> Arc<Notify> {
+
+    let mut u_data = ctx.data.write().await;
+
+    u_data.entry::<Playback>()
+          .or_insert_with(HashMap::new)
+          .entry(guild_id)
+          .or_default()
+          .clone()
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Wakes everything that is waiting for a sound to end in a guild
+async fn interruptPlayback(ctx: &Context, guild_id: GuildId) {
+
+    if let Some(N) = ctx.data.read().await.get::<Playback>().and_then(|p| p.get(&guild_id)) {
+        N.notify_waiters();
+    }
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// STFU, stops whatever is playing in the guild of the author
+pub async fn stopSound(inv: &Invocation) -> Result<(), RinrError> {
+
+    let guild_id: GuildId = match inv.guild_id {
+        Some(G) => G,
+        None => return Ok(()),
+    };
+
+    clearQueue(&inv.data, guild_id).await;
+
+    // Stays quiet when it worked, that is the point
+    if !inv.gateway.stop(guild_id).await? {
+        inv.reply("voice.not_playing", &[]).await;
+    }
+
+    Ok(())
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Forces the bot out of voice in the guild of the author
+pub async fn disconnectVoice(inv: &Invocation) -> Result<(), RinrError> {
+
+    let guild_id: GuildId = match inv.guild_id {
+        Some(G) => G,
+        None => return Ok(()),
+    };
+
+    clearQueue(&inv.data, guild_id).await;
+
+    match inv.gateway.leave(guild_id).await? {
+        true => inv.reply("voice.disconnected", &[]).await,
+        false => inv.reply("voice.not_connected", &[]).await,
+    }
+
+    Ok(())
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Shows or sets the volume sounds are played at in the guild of the author
+pub async fn volumeHandler(inv: &Invocation, args: &ParsedArgs) -> Result<(), RinrError> {
+
+    let guild_id: GuildId = match inv.guild_id {
+        Some(G) => G,
+        None => return Ok(()),
+    };
+
+    let config: Arc<Mutex<RinrOptions>> = match inv.data.read().await.get::<RinrOptions>() {
+        Some(C) => C.clone(),
+        None => return Ok(()),
+    };
+
+    let percent: u64 = match args.int("percent") {
+        Some(P) => P as u64,
+        None => {
+            let current: u64 = config.lock().unwrap().volumeFor(guild_id.get());
+            inv.reply("voice.volume", &[("percent", &current.to_string())]).await;
+            return Ok(());
+        },
+    };
+
+    config.lock().unwrap().guilds.entry(guild_id.get()).or_default().volume = Some(percent);
+
+    saveConfig(&config).await?;
+
+    inv.reply("voice.volume_set", &[("percent", &percent.to_string())]).await;
+
+    Ok(())
+}
+
+
+//--------------------------------------------------------------------------------------------------------------------------
+// Shows or changes which sounds play in the guild of the author and how outros behave
+pub async fn soundsHandler(inv: &Invocation, args: &ParsedArgs) -> Result<(), RinrError> {
+
+    let guild_id: GuildId = match inv.guild_id {
+        Some(G) => G,
+        None => return Ok(()),
+    };
+
+    let config: Arc<Mutex<RinrOptions>> = match inv.data.read().await.get::<RinrOptions>() {
+        Some(C) => C.clone(),
+        None => return Ok(()),
+    };
+
+    let kind: Option<SoundKind> = args.text("kind").and_then(|k| k.parse::<SoundKind>().ok());
+    let changes: bool = args.has("enabled") || args.has("delay") || args.has("empty");
+
+    if kind.is_some() && !args.has("enabled") {
+        return Err(ArgError::new("enabled", ArgReason::RequiredFor("kind".to_string())).into());
+    }
+
+    let options: SoundOptions = {
+        let mut config = config.lock().unwrap();
+
+        if changes {
+            let sounds: &mut SoundOptions = &mut config.guilds.entry(guild_id.get()).or_default().sounds;
+
+            match (kind, args.bool("enabled")) {
+                (Some(SoundKind::Intro), Some(E)) => sounds.intros = E,
+                (Some(SoundKind::Outro), Some(E)) => sounds.outros = E,
+                (Some(SoundKind::Move), Some(E)) => sounds.moves = E,
+                _ => (),
+            }
+
+            if let Some(D) = args.timestamp("delay") {
+                sounds.outro_delay = D;
+            }
+
+            if let Some(E) = args.bool("empty") {
+                sounds.outro_when_empty = E;
+            }
+        }
+
+        config.soundsFor(guild_id.get())
+    };
+
+    if changes {
+        saveConfig(&config).await?;
+    }
+
+    let lang: Lang = inv.lang().await;
+    let state = |on: bool| tr(lang, if on {"sounds.on"} else {"sounds.off"}, &[]);
+
+    inv.say(tr(lang, "sounds.settings", &[
+        ("intros", &state(options.intros)),
+        ("outros", &state(options.outros)),
+        ("moves", &state(options.moves)),
+        ("delay", &formatTimestamp(options.outro_delay)),
+        ("empty", &state(options.outro_when_empty)),
+    ])).await;
+
     Ok(())
 }
 
@@ -123,64 +514,95 @@ pub async fn removeManager(manager: &Arc<Songbird>, guild_id: GuildId) {
 
 }
 
-//--------------------------------------------------------------------------------------------------------------------------
-// Checks if the bot already is in a channel
-pub async fn checkDuplicate(guild_channels: Result<Vec<GuildChannel>, serenity::prelude::SerenityError>, cache: &Arc<Cache>) -> bool {
 
-    // Loop over all channels in guild
-    for c in guild_channels.unwrap() {
-        
-        // Continue only if Voice Channel
-        if c.kind != ChannelType::Voice {
-            continue;
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveDate, NaiveTime};
+    use crate::clock::{ClockKey, FakeClock};
+    use crate::gateway::MemoryGateway;
 
-        // Get member Vector of Voice Channel
-        let chan_members: Result<Vec<Member>, serenity::Error> = c.members(&cache);
+    const GUILD: GuildId = GuildId::new(1);
+    const CHANNEL: ChannelId = ChannelId::new(2);
 
-        // Check if Vector is empty
-        let members: Vec<Member> = match chan_members {
-            Ok(M) => M,
-            Err(_) => continue, 
-        };
+    fn noon() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 1, 1).unwrap().and_hms_opt(12, 0, 0).unwrap()
+    }
 
-        // Loop over Vector
-        for m in members {
-            
-            // Check if Bot is in a channel already, if it is an error is returned
-            if m.user.id != BOT_ID {
-                continue;
-            }
+    fn state(policy: IntroPolicy) -> Arc<RwLock<TypeMap>> {
 
-            return true;
-        }
+        let mut options: RinrOptions = RinrOptions::default();
+        options.guilds.insert(GUILD.get(), GuildOptions { policy, ..GuildOptions::default() });
+
+        let mut data: TypeMap = TypeMap::new();
+        data.insert::<RinrOptions>(Arc::new(Mutex::new(options)));
+        data.insert::<ClockKey>(Arc::new(FakeClock::new(noon())));
+        Arc::new(RwLock::new(data))
     }
 
-    false
-}
+    async fn allowed(gateway: &MemoryGateway, data: &RwLock<TypeMap>, kind: SoundKind) -> bool {
+        checkPolicy(gateway, data, kind, GUILD, 10, CHANNEL).await.unwrap()
+    }
 
+    #[tokio::test]
+    async fn policyChecksChannelsAndQuietHours() {
 
-//--------------------------------------------------------------------------------------------------------------------------
-// Checks conditions for playing an intro
-fn checkError(user_id: u64, old: Option<VoiceState>, new: &VoiceState) -> Result<ChannelId, Error> {
+        let gateway: MemoryGateway = MemoryGateway::default();
 
-    // Checks if user joined is the bot
-    if user_id == BOT_ID {
-        return Err(Error);
+        let data: Arc<RwLock<TypeMap>> = state(IntroPolicy { denied_channels: vec![CHANNEL.get()], ..IntroPolicy::default() });
+        assert!(!allowed(&gateway, &data, SoundKind::Intro).await);
+
+        let data: Arc<RwLock<TypeMap>> = state(IntroPolicy { allowed_channels: vec![3], ..IntroPolicy::default() });
+        assert!(!allowed(&gateway, &data, SoundKind::Intro).await);
+
+        let quiet: QuietHours = QuietHours { start: NaiveTime::from_hms_opt(11, 0, 0).unwrap(), end: NaiveTime::from_hms_opt(13, 0, 0).unwrap() };
+        let data: Arc<RwLock<TypeMap>> = state(IntroPolicy { quiet_hours: vec![quiet], ..IntroPolicy::default() });
+        assert!(!allowed(&gateway, &data, SoundKind::Intro).await);
+
+        let data: Arc<RwLock<TypeMap>> = state(IntroPolicy::default());
+        assert!(allowed(&gateway, &data, SoundKind::Intro).await);
     }
 
-    // Checks if user wasn't in a channel before
-    match old {
-        Some(_) => return Err(Error),
-        None => (),
+    #[tokio::test]
+    async fn policyCountsListenersWithoutTheUser() {
+
+        let gateway: MemoryGateway = MemoryGateway::default();
+        let data: Arc<RwLock<TypeMap>> = state(IntroPolicy { min_listeners: 1, ..IntroPolicy::default() });
+
+        // Alone in the channel, nobody to hear the intro but someone is left to hear the outro
+        gateway.voice.lock().unwrap().insert((GUILD, UserId::new(10)), CHANNEL);
+        assert!(!allowed(&gateway, &data, SoundKind::Intro).await);
+
+        gateway.voice.lock().unwrap().remove(&(GUILD, UserId::new(10)));
+        gateway.voice.lock().unwrap().insert((GUILD, UserId::new(11)), CHANNEL);
+        assert!(allowed(&gateway, &data, SoundKind::Outro).await);
     }
 
-    // Checks if user is in new channel now and gets ID
-    let channel_id: ChannelId = match new.channel_id {
-        Some(S) => S,
-        None => return Err(Error),
-    };
+    #[tokio::test]
+    async fn cooldownIsPerKind() {
 
-    Ok(channel_id)
-}
+        let gateway: MemoryGateway = MemoryGateway::default();
+        let data: Arc<RwLock<TypeMap>> = state(IntroPolicy { rejoin_cooldown: 60, ..IntroPolicy::default() });
+
+        let last: NaiveDateTime = noon() - chrono::Duration::seconds(30);
+        data.write().await.insert::<LastSounds>(HashMap::from([((GUILD, 10, SoundKind::Move), last)]));
+
+        assert!(!allowed(&gateway, &data, SoundKind::Move).await);
+        assert!(allowed(&gateway, &data, SoundKind::Intro).await);
 
+        let last: NaiveDateTime = noon() - chrono::Duration::seconds(60);
+        data.write().await.insert::<LastSounds>(HashMap::from([((GUILD, 10, SoundKind::Move), last)]));
+        assert!(allowed(&gateway, &data, SoundKind::Move).await);
+    }
+
+    #[tokio::test]
+    async fn nothingQueuedStartsNoCooldown() {
+
+        let gateway: Arc<dyn Gateway> = Arc::new(MemoryGateway::default());
+        let data: Arc<RwLock<TypeMap>> = state(IntroPolicy { rejoin_cooldown: 60, ..IntroPolicy::default() });
+
+        // Without an intro library there is nothing to play
+        assert!(!voiceEvent(&gateway, &data, GUILD, 10, None, Some(CHANNEL)).await.unwrap());
+        assert!(data.read().await.get::<LastSounds>().is_none());
+    }
+}